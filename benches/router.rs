@@ -0,0 +1,45 @@
+//! Compares the dispatch through `Router` with the chain of `or` for 32 routes.
+
+#![feature(test)]
+
+extern crate finchers;
+extern crate test;
+
+use finchers::endpoint::syntax;
+use finchers::local;
+use finchers::prelude::*;
+use finchers::{router, routes};
+
+use test::Bencher;
+
+macro_rules! segment_route {
+    ($name:expr) => {
+        syntax::segment($name)
+            .and(syntax::param::<u32>())
+            .and(syntax::eos())
+    };
+}
+
+macro_rules! define_benches {
+    ($($name:expr),*) => {
+        #[bench]
+        fn or_chain(b: &mut Bencher) {
+            let endpoint = routes![$( segment_route!($name) ),*];
+            b.iter(|| local::get("/r31/42").apply(&endpoint).unwrap());
+        }
+
+        #[bench]
+        fn router(b: &mut Bencher) {
+            let endpoint = router! {
+                $( $name => syntax::param::<u32>().and(syntax::eos()) ),*
+            };
+            b.iter(|| local::get("/r31/42").apply(&endpoint).unwrap());
+        }
+    };
+}
+
+define_benches!(
+    "r00", "r01", "r02", "r03", "r04", "r05", "r06", "r07", "r08", "r09", "r10", "r11", "r12",
+    "r13", "r14", "r15", "r16", "r17", "r18", "r19", "r20", "r21", "r22", "r23", "r24", "r25",
+    "r26", "r27", "r28", "r29", "r30", "r31"
+);
@@ -1,44 +1,31 @@
 #![feature(async_await, futures_api)]
 
-use finchers::error::{fail, Error};
-use finchers::output::payload::Once;
+use finchers::output::template::{Renderer, Template};
 use finchers::prelude::*;
 use finchers::{path, routes};
 
 use failure::SyncFailure;
-use http::Response;
-use std::sync::Arc;
 use tera::{compile_templates, Context, Tera};
 
-fn main() {
-    let tera = endpoint::value(Arc::new(compile_templates!(concat!(
-        env!("CARGO_MANIFEST_DIR"),
-        "/templates/**/*"
-    ))));
+const TEMPLATES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/templates/**/*");
 
-    let index = path!(@get /)
-        .and(tera.clone())
-        .map(|tera| render_template(tera, "index.html"));
+fn main() {
+    let index = path!(@get /).map(|| Template::new("index.html", Context::new()));
 
-    let detail = path!(@get /"detail"/)
-        .and(tera.clone())
-        .map(|tera| render_template(tera, "detail.html"));
+    let detail = path!(@get /"detail"/).map(|| Template::new("detail.html", Context::new()));
 
-    let p404 = endpoint::syntax::verb::get()
-        .and(tera.clone())
-        .map(|tera| render_template(tera, "404.html"));
+    let p404 = endpoint::syntax::verb::get().map(|| Template::new("404.html", Context::new()));
 
     let endpoint = routes![index, detail, p404];
 
-    finchers::launch(endpoint).start("127.0.0.1:4000")
-}
+    // Reload the templates from the disk at each request during development.
+    let renderer = if cfg!(debug_assertions) {
+        Renderer::reloading(|| Tera::new(TEMPLATES).map_err(|err| SyncFailure::new(err).into()))
+    } else {
+        Renderer::new(compile_templates!(TEMPLATES))
+    };
 
-fn render_template(tera: Arc<Tera>, template: &str) -> Result<Response<Once<String>>, Error> {
-    tera.render(template, &Context::default())
-        .map(|body| {
-            Response::builder()
-                .header("content-type", "text/html; charset=utf-8")
-                .body(Once::new(body))
-                .unwrap()
-        }).map_err(|err| fail(SyncFailure::new(err)))
+    finchers::launch(endpoint)
+        .manage(renderer)
+        .start("127.0.0.1:4000")
 }
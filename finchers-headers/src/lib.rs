@@ -32,6 +32,8 @@ use std::marker::PhantomData;
 
 use crate::header::TypedHeader;
 
+pub mod precondition;
+
 /// Create an endpoint which parses a header field to the specified type.
 pub fn header<T>() -> Header<T>
 where
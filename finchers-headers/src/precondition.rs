@@ -0,0 +1,355 @@
+//! Components for evaluating the preconditions of requests (RFC 7232).
+//!
+//! The endpoint created by `conditions()` extracts `If-Match` and
+//! `If-Unmodified-Since` from the request. The handler evaluates them against
+//! the current `Version` of the target resource with `Conditions::check()`
+//! before applying the changes, and wraps the successful output in `Versioned`
+//! in order to send the new `ETag` and `Last-Modified` to the client.
+
+use futures::future::{ready, Ready};
+use http::header::{HeaderMap, HeaderValue};
+use http::{header, Response, StatusCode};
+use std::fmt;
+use std::time::SystemTime;
+
+use finchers::endpoint::{Context, Endpoint, EndpointError, EndpointResult};
+use finchers::error::{bad_request, Error, HttpError};
+use finchers::input::Input;
+use finchers::output::{Output, OutputContext};
+
+use crate::header::{EntityTag, HttpDate, IfMatch, IfUnmodifiedSince, TypedHeader};
+
+/// Create an endpoint which extracts the precondition headers from the request.
+pub fn conditions() -> ConditionsEndpoint {
+    ConditionsEndpoint { required: false }
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone)]
+pub struct ConditionsEndpoint {
+    required: bool,
+}
+
+impl ConditionsEndpoint {
+    /// Rejects the requests without any precondition headers with `428 Precondition Required`.
+    pub fn required(self) -> ConditionsEndpoint {
+        ConditionsEndpoint { required: true }
+    }
+}
+
+impl<'a> Endpoint<'a> for ConditionsEndpoint {
+    type Output = (Conditions,);
+    type Future = Ready<Result<Self::Output, Error>>;
+
+    fn apply(&self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let conditions = Conditions::from_input(&*cx.input()).map_err(EndpointError::custom)?;
+        if self.required && conditions.is_empty() {
+            return Err(EndpointError::custom(PreconditionRequired { _priv: () }));
+        }
+        Ok(ready(Ok((conditions,))))
+    }
+}
+
+/// The precondition headers sent with a request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conditions {
+    if_match: Option<IfMatch>,
+    if_unmodified_since: Option<HttpDate>,
+}
+
+impl Conditions {
+    fn from_input(input: &Input) -> Result<Conditions, Error> {
+        fn parse<T: TypedHeader>(input: &Input) -> Result<Option<T::Output>, Error> {
+            match input.headers().get(T::NAME) {
+                Some(h) => T::parse_header(h)
+                    .map(Some)
+                    .map_err(|err| bad_request(err.into())),
+                None => Ok(None),
+            }
+        }
+
+        Ok(Conditions {
+            if_match: parse::<IfMatch>(input)?,
+            // An invalid `If-Unmodified-Since` must be ignored (RFC 7232, section 3.4).
+            if_unmodified_since: parse::<IfUnmodifiedSince>(input).ok().and_then(|date| date),
+        })
+    }
+
+    /// Returns the value of `If-Match`, if present.
+    pub fn if_match(&self) -> Option<&IfMatch> {
+        self.if_match.as_ref()
+    }
+
+    /// Returns the value of `If-Unmodified-Since`, if present.
+    pub fn if_unmodified_since(&self) -> Option<SystemTime> {
+        self.if_unmodified_since.map(Into::into)
+    }
+
+    /// Returns `true` if the request has no precondition headers.
+    pub fn is_empty(&self) -> bool {
+        self.if_match.is_none() && self.if_unmodified_since.is_none()
+    }
+
+    /// Evaluates the preconditions against the current version of the target resource.
+    ///
+    /// `current` is `None` if the target resource does not exist.
+    /// The evaluation follows the section 6 of RFC 7232, i.e. `If-Unmodified-Since`
+    /// is ignored if `If-Match` is present.
+    pub fn check(&self, current: Option<&Version>) -> Result<(), PreconditionFailed> {
+        let passed = match (&self.if_match, &self.if_unmodified_since) {
+            (Some(IfMatch::Any), _) => current.is_some(),
+            (Some(IfMatch::Items(tags)), _) => current
+                .and_then(|version| version.etag.as_ref())
+                .map_or(false, |etag| tags.iter().any(|tag| tag.strong_eq(etag))),
+            (None, Some(since)) => current
+                .and_then(|version| version.last_modified)
+                .map_or(true, |last_modified| {
+                    HttpDate::from(last_modified) <= *since
+                }),
+            (None, None) => true,
+        };
+        if passed {
+            Ok(())
+        } else {
+            Err(PreconditionFailed { _priv: () })
+        }
+    }
+}
+
+/// The version information of a resource.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Version {
+    etag: Option<EntityTag>,
+    last_modified: Option<SystemTime>,
+}
+
+impl Version {
+    /// Creates an empty `Version`.
+    pub fn new() -> Version {
+        Version::default()
+    }
+
+    /// Sets the entity tag of the resource.
+    pub fn etag(self, etag: EntityTag) -> Version {
+        Version {
+            etag: Some(etag),
+            ..self
+        }
+    }
+
+    /// Sets the last modification date of the resource.
+    pub fn last_modified(self, last_modified: SystemTime) -> Version {
+        Version {
+            last_modified: Some(last_modified),
+            ..self
+        }
+    }
+
+    fn set_headers(&self, headers: &mut HeaderMap) {
+        if let Some(ref etag) = self.etag {
+            if let Ok(value) = HeaderValue::from_str(&etag.to_string()) {
+                headers.insert(header::ETAG, value);
+            }
+        }
+        if let Some(last_modified) = self.last_modified {
+            let date = HttpDate::from(last_modified).to_string();
+            if let Ok(value) = HeaderValue::from_str(&date) {
+                headers.insert(header::LAST_MODIFIED, value);
+            }
+        }
+    }
+}
+
+/// An instance of `Output` which attaches `ETag` and `Last-Modified` to the response.
+#[derive(Debug)]
+pub struct Versioned<T> {
+    value: T,
+    version: Version,
+}
+
+impl<T> Versioned<T> {
+    /// Creates a new `Versioned` from the output value and its version.
+    pub fn new(value: T, version: Version) -> Versioned<T> {
+        Versioned { value, version }
+    }
+}
+
+impl<T: Output> Output for Versioned<T> {
+    type Body = T::Body;
+    type Error = T::Error;
+
+    fn respond(self, cx: &mut OutputContext<'_>) -> Result<Response<Self::Body>, Self::Error> {
+        let mut response = self.value.respond(cx)?;
+        self.version.set_headers(response.headers_mut());
+        Ok(response)
+    }
+}
+
+/// An error which represents that the preconditions of the request were evaluated to false.
+#[derive(Debug)]
+pub struct PreconditionFailed {
+    _priv: (),
+}
+
+impl fmt::Display for PreconditionFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("precondition failed")
+    }
+}
+
+impl HttpError for PreconditionFailed {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::PRECONDITION_FAILED
+    }
+}
+
+/// An error which represents that the request is required to be conditional.
+#[derive(Debug)]
+pub struct PreconditionRequired {
+    _priv: (),
+}
+
+impl fmt::Display for PreconditionRequired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the request is required to have If-Match or If-Unmodified-Since")
+    }
+}
+
+impl HttpError for PreconditionRequired {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::PRECONDITION_REQUIRED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use finchers::endpoint::wrapper::EndpointWrapExt;
+    use finchers::local;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn conditions_of(if_match: Option<IfMatch>, since: Option<SystemTime>) -> Conditions {
+        Conditions {
+            if_match,
+            if_unmodified_since: since.map(HttpDate::from),
+        }
+    }
+
+    fn date(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_if_match_any() {
+        let conditions = conditions_of(Some(IfMatch::Any), None);
+        assert!(conditions.check(None).is_err());
+        assert!(conditions.check(Some(&Version::new())).is_ok());
+    }
+
+    #[test]
+    fn test_if_match_strong_comparison() {
+        let strong = Version::new().etag(EntityTag::strong("v1".into()));
+        let weak = Version::new().etag(EntityTag::weak("v1".into()));
+
+        let conditions = conditions_of(
+            Some(IfMatch::Items(vec![EntityTag::strong("v1".into())])),
+            None,
+        );
+        assert!(conditions.check(Some(&strong)).is_ok());
+        assert!(conditions.check(Some(&weak)).is_err());
+        assert!(conditions.check(Some(&Version::new())).is_err());
+        assert!(conditions.check(None).is_err());
+
+        let conditions = conditions_of(
+            Some(IfMatch::Items(vec![EntityTag::weak("v1".into())])),
+            None,
+        );
+        assert!(conditions.check(Some(&strong)).is_err());
+    }
+
+    #[test]
+    fn test_if_unmodified_since() {
+        let version = Version::new().last_modified(date(1_500_000_000));
+
+        let conditions = conditions_of(None, Some(date(1_500_000_000)));
+        assert!(conditions.check(Some(&version)).is_ok());
+
+        let conditions = conditions_of(None, Some(date(1_499_999_999)));
+        assert!(conditions.check(Some(&version)).is_err());
+    }
+
+    #[test]
+    fn test_if_match_takes_precedence() {
+        let version = Version::new()
+            .etag(EntityTag::strong("v1".into()))
+            .last_modified(date(1_500_000_000));
+
+        // `If-Unmodified-Since` is ignored even if it is evaluated to false.
+        let conditions = conditions_of(
+            Some(IfMatch::Items(vec![EntityTag::strong("v1".into())])),
+            Some(date(1_499_999_999)),
+        );
+        assert!(conditions.check(Some(&version)).is_ok());
+
+        let conditions = conditions_of(
+            Some(IfMatch::Items(vec![EntityTag::strong("v0".into())])),
+            Some(date(1_500_000_000)),
+        );
+        assert!(conditions.check(Some(&version)).is_err());
+    }
+
+    #[test]
+    fn test_invalid_if_unmodified_since() {
+        let (conditions,) = local::put("/")
+            .header("if-unmodified-since", "yesterday")
+            .apply(&conditions())
+            .expect("the invalid date should be ignored");
+        assert_eq!(conditions.if_unmodified_since(), None);
+        assert!(conditions.is_empty());
+    }
+
+    #[test]
+    fn test_endpoint() {
+        let current = Version::new().etag(EntityTag::strong("v1".into()));
+        let endpoint = conditions()
+            .required()
+            .and_then(move |conditions: Conditions| {
+                let updated = Version::new()
+                    .etag(EntityTag::strong("v2".into()))
+                    .last_modified(date(1_500_000_000));
+                ready(
+                    conditions
+                        .check(Some(&current))
+                        .map(|()| Versioned::new("updated", updated))
+                        .map_err(Into::into),
+                )
+            });
+
+        let err = local::put("/")
+            .apply(&endpoint)
+            .err()
+            .expect("the request without preconditions should be rejected");
+        assert_eq!(err.status_code(), StatusCode::PRECONDITION_REQUIRED);
+
+        let response = local::put("/")
+            .header("if-match", "\"v0\"")
+            .respond(&endpoint);
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+
+        let response = local::put("/")
+            .header("if-match", "\"v1\"")
+            .respond(&endpoint);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("etag").map(|h| h.as_bytes()),
+            Some(&b"\"v2\""[..])
+        );
+        assert_eq!(
+            response
+                .headers()
+                .get("last-modified")
+                .map(|h| h.as_bytes()),
+            Some(&b"Fri, 14 Jul 2017 02:40:00 GMT"[..])
+        );
+    }
+}
@@ -5,19 +5,27 @@
 use std::future::Future;
 use std::io;
 use std::pin::PinMut;
+use std::sync::Arc;
 use std::task;
 use std::task::Poll;
+use std::time::{Duration, Instant};
 
+use futures::Future as Future01;
 use futures_core::future::TryFuture;
 use futures_util::ready;
 use http::header::HeaderValue;
-use http::{header, Request, Response};
+use http::{header, Request, Response, StatusCode};
+use log::error;
+use tokio::timer::Delay;
 
-use crate::common::Either;
+use crate::common::{poll_01_with_cx, Either, StateMap};
 use crate::endpoint::{Context, Endpoint};
-use crate::error::Error;
+use crate::endpoints::request_id::RequestId;
+use crate::endpoints::session::Session;
+use crate::endpoints::timeout::TimedOut;
+use crate::error::{fail, Error};
 use crate::input::body::ReqBody;
-use crate::input::{with_set_cx, Input};
+use crate::input::{with_set_cx, ConnectionInfo, Input};
 use crate::output::payload::Once;
 use crate::output::{Output, OutputContext};
 
@@ -25,20 +33,69 @@ use crate::output::{Output, OutputContext};
 #[derive(Debug)]
 pub struct App<'e, E: Endpoint<'e>> {
     endpoint: &'e E,
+    connection: Option<ConnectionInfo>,
+    state: Option<Arc<StateMap>>,
+    timeouts: Timeouts,
+}
+
+/// The time limits applied to each request.
+#[derive(Debug, Copy, Clone, Default)]
+pub(crate) struct Timeouts {
+    pub(crate) body_read: Option<Duration>,
+    pub(crate) request: Option<Duration>,
 }
 
 impl<'e, E: Endpoint<'e>> App<'e, E> {
     /// Create a new `App` from the provided components.
     pub fn new(endpoint: &'e E) -> App<'e, E> {
-        App { endpoint }
+        App {
+            endpoint,
+            connection: None,
+            state: None,
+            timeouts: Timeouts::default(),
+        }
+    }
+
+    /// Create a new `App` which serves the requests received on a connection.
+    pub fn with_connection(endpoint: &'e E, connection: ConnectionInfo) -> App<'e, E> {
+        App {
+            endpoint,
+            connection: Some(connection),
+            state: None,
+            timeouts: Timeouts::default(),
+        }
+    }
+
+    pub(crate) fn with_state(self, state: Arc<StateMap>) -> App<'e, E> {
+        App {
+            state: Some(state),
+            ..self
+        }
+    }
+
+    pub(crate) fn with_timeouts(self, timeouts: Timeouts) -> App<'e, E> {
+        App { timeouts, ..self }
     }
 
     #[allow(missing_docs)]
-    pub fn dispatch_request(&self, request: Request<ReqBody>) -> AppFuture<'e, E> {
+    pub fn dispatch_request(&self, mut request: Request<ReqBody>) -> AppFuture<'e, E> {
+        if let Some(connection) = self.connection {
+            request.extensions_mut().insert(connection);
+        }
+        if let Some(ref state) = self.state {
+            request.extensions_mut().insert(state.clone());
+        }
+        if let Some(timeout) = self.timeouts.body_read {
+            request.body_mut().set_timeout(timeout);
+        }
         AppFuture {
             state: State::Uninitialized,
             input: Input::new(request),
             endpoint: self.endpoint,
+            deadline: self
+                .timeouts
+                .request
+                .map(|timeout| (timeout, Delay::new(Instant::now() + timeout))),
         }
     }
 }
@@ -52,6 +109,7 @@ pub struct AppFuture<'e, E: Endpoint<'e>> {
     state: State<E::Future>,
     input: Input,
     endpoint: &'e E,
+    deadline: Option<(Duration, Delay)>,
 }
 
 #[derive(Debug)]
@@ -86,7 +144,10 @@ where
                 }
                 State::InFlight(ref mut f) => {
                     let f = unsafe { PinMut::new_unchecked(f) };
-                    break with_set_cx(input.reborrow(), || f.try_poll(cx));
+                    if let Poll::Ready(result) = with_set_cx(input.reborrow(), || f.try_poll(cx)) {
+                        break Poll::Ready(result);
+                    }
+                    break poll_deadline(&mut this.deadline, cx);
                 }
                 State::Gone => panic!("cannot poll AppServiceFuture twice"),
             }
@@ -104,6 +165,7 @@ where
 
         let this = unsafe { PinMut::get_mut_unchecked(self) };
         let mut input = unsafe { PinMut::new_unchecked(&mut this.input) };
+        let request_id = input.extensions().get::<RequestId>().cloned();
         let mut response = output
             .and_then({
                 let mut cx = OutputContext::new(input.reborrow());
@@ -112,12 +174,31 @@ where
                         .map(|res| res.map(Either::Right))
                         .map_err(Into::into)
                 }
-            }).unwrap_or_else(|err| err.to_response().map(|body| Either::Left(Once::new(body))));
+            }).unwrap_or_else(|mut err| {
+                if let Some(ref request_id) = request_id {
+                    err.set_request_id(request_id.as_str());
+                }
+                err.to_response().map(|body| Either::Left(Once::new(body)))
+            });
+
+        if let Some(ref request_id) = request_id {
+            request_id.set_header(response.headers_mut());
+        }
+
+        if let Some(session) = input.reborrow().extensions_mut().remove::<Session>() {
+            if let Err(err) = input
+                .reborrow()
+                .cookies()
+                .and_then(|mut cookies| session.persist(&mut cookies))
+            {
+                error!("failed to persist the session: {}", err);
+            }
+        }
 
         if let Some(jar) = input.cookie_jar() {
             for cookie in jar.delta() {
                 let val = HeaderValue::from_str(&cookie.encoded().to_string()).unwrap();
-                response.headers_mut().insert(header::SET_COOKIE, val);
+                response.headers_mut().append(header::SET_COOKIE, val);
             }
         }
 
@@ -134,6 +215,25 @@ where
     }
 }
 
+/// Returns an error if the time limit of the whole request has elapsed.
+fn poll_deadline<T>(
+    deadline: &mut Option<(Duration, Delay)>,
+    cx: &mut task::Context<'_>,
+) -> Poll<Result<T, Error>> {
+    let (timeout, delay) = match *deadline {
+        Some((timeout, ref mut delay)) => (timeout, delay),
+        None => return Poll::Pending,
+    };
+    match poll_01_with_cx(cx, || delay.poll()) {
+        Poll::Ready(Ok(())) => {
+            let err = TimedOut::new(StatusCode::SERVICE_UNAVAILABLE, timeout);
+            Poll::Ready(Err(err.into()))
+        }
+        Poll::Ready(Err(err)) => Poll::Ready(Err(fail(err))),
+        Poll::Pending => Poll::Pending,
+    }
+}
+
 impl<'e, E> Future for AppFuture<'e, E>
 where
     E: Endpoint<'e>,
@@ -177,6 +277,9 @@ mod service {
         fn new_service(&self) -> Self::Future {
             futures01::future::ok(App {
                 endpoint: self.endpoint,
+                connection: self.connection,
+                state: self.state.clone(),
+                timeouts: self.timeouts,
             })
         }
     }
@@ -0,0 +1,50 @@
+use futures::{self as futures01, Async};
+use std::task::{self, Poll};
+
+/// Polls a futures-0.1 value with the waker of the specified task context.
+pub(crate) fn poll_01_with_cx<T, E>(
+    cx: &mut task::Context<'_>,
+    f: impl FnOnce() -> futures01::Poll<T, E>,
+) -> Poll<Result<T, E>> {
+    // FIXME: Set the executor to the global context for futures-0.1
+    let notify = &WakerToHandle(cx.waker());
+
+    futures01::executor::with_notify(notify, 0, move || match f() {
+        Ok(Async::Ready(ok)) => Poll::Ready(Ok(ok)),
+        Ok(Async::NotReady) => Poll::Pending,
+        Err(err) => Poll::Ready(Err(err)),
+    })
+}
+
+#[allow(missing_debug_implementations)]
+struct NotifyWaker(task::Waker);
+
+#[derive(Clone)]
+#[allow(missing_debug_implementations)]
+struct WakerToHandle<'a>(&'a task::Waker);
+
+impl<'a> From<WakerToHandle<'a>> for futures01::executor::NotifyHandle {
+    fn from(handle: WakerToHandle<'a>) -> futures01::executor::NotifyHandle {
+        let ptr = Box::new(NotifyWaker(handle.0.clone()));
+        unsafe { futures01::executor::NotifyHandle::new(Box::into_raw(ptr)) }
+    }
+}
+
+impl futures01::executor::Notify for NotifyWaker {
+    fn notify(&self, _: usize) {
+        self.0.wake()
+    }
+}
+
+unsafe impl futures01::executor::UnsafeNotify for NotifyWaker {
+    unsafe fn clone_raw(&self) -> futures01::executor::NotifyHandle {
+        WakerToHandle(&self.0).into()
+    }
+
+    unsafe fn drop_raw(&self) {
+        let ptr: *const dyn futures01::executor::UnsafeNotify = self;
+        drop(Box::from_raw(
+            ptr as *mut dyn futures01::executor::UnsafeNotify,
+        ));
+    }
+}
@@ -1,9 +1,16 @@
 mod combine;
+mod compat;
 mod either;
 mod func;
 mod hlist;
+mod state;
+mod token;
 
 pub use self::combine::Combine;
 pub use self::either::Either;
 pub use self::func::Func;
 pub use self::hlist::Tuple;
+
+pub(crate) use self::compat::poll_01_with_cx;
+pub(crate) use self::state::StateMap;
+pub(crate) use self::token::{constant_time_eq, random_token};
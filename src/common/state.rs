@@ -0,0 +1,56 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// A type map which stores the application-wide shared values.
+#[derive(Clone, Default)]
+pub(crate) struct StateMap {
+    map: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl fmt::Debug for StateMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateMap")
+            .field("len", &self.map.len())
+            .finish()
+    }
+}
+
+impl StateMap {
+    pub(crate) fn insert<T>(&mut self, value: T)
+    where
+        T: Send + Sync + 'static,
+    {
+        self.map.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    pub(crate) fn get<T>(&self) -> Option<Arc<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.map
+            .get(&TypeId::of::<T>())
+            .cloned()
+            .and_then(|value| value.downcast::<T>().ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_map() {
+        let mut state = StateMap::default();
+        state.insert(42u32);
+        state.insert(String::from("foo"));
+
+        assert_eq!(state.get::<u32>().map(|v| *v), Some(42));
+        assert_eq!(
+            state.get::<String>().map(|v| (*v).clone()),
+            Some("foo".into())
+        );
+        assert!(state.get::<u64>().is_none());
+    }
+}
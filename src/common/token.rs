@@ -0,0 +1,36 @@
+use rand::{thread_rng, Rng};
+
+/// Generates a random token which consists of `len` bytes, encoded as a hexadecimal string.
+pub(crate) fn random_token(len: usize) -> String {
+    let mut buf = vec![0u8; len];
+    thread_rng().fill(&mut buf[..]);
+    buf.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Compares two byte sequences in constant time with respect to their contents.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_token() {
+        let token = random_token(16);
+        assert_eq!(token.len(), 32);
+        assert!(token.bytes().all(|b| b.is_ascii_hexdigit()));
+        assert_ne!(token, random_token(16));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"foo", b"foo"));
+        assert!(!constant_time_eq(b"foo", b"bar"));
+        assert!(!constant_time_eq(b"foo", b"fooo"));
+    }
+}
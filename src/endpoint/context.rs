@@ -11,7 +11,7 @@ pub(crate) struct Cursor {
 }
 
 impl Cursor {
-    fn clone(&self) -> Cursor {
+    pub(crate) fn clone(&self) -> Cursor {
         Cursor { ..*self }
     }
 }
@@ -21,6 +21,7 @@ impl Cursor {
 pub struct Context<'a> {
     input: PinMut<'a, Input>,
     cursor: Cursor,
+    mount_pos: usize,
     _marker: PhantomData<Rc<()>>,
 }
 
@@ -31,6 +32,7 @@ impl<'a> Context<'a> {
         Context {
             input,
             cursor: Cursor { pos: 1, popped: 0 },
+            mount_pos: 0,
             _marker: PhantomData,
         }
     }
@@ -47,6 +49,20 @@ impl<'a> Context<'a> {
         unsafe { EncodedStr::new_unchecked(&self.input.uri().path()[self.cursor.pos..]) }
     }
 
+    /// Returns the path prefix where the current endpoint is mounted, without the trailing slash.
+    ///
+    /// The returned value is percent-encoded, and is empty if the endpoint
+    /// is not mounted by `endpoint::mount()` or `Router::route()`.
+    #[inline]
+    pub fn mount_point(&self) -> &str {
+        let path = &self.input.uri().path()[..self.mount_pos];
+        if path.ends_with('/') {
+            &path[..path.len() - 1]
+        } else {
+            path
+        }
+    }
+
     /// Advances the cursor and returns the next segment.
     #[inline]
     pub fn next_segment(&mut self) -> Option<&EncodedStr> {
@@ -77,6 +93,7 @@ impl<'a> Context<'a> {
         Context {
             input: self.input.reborrow(),
             cursor: self.cursor.clone(),
+            mount_pos: self.mount_pos,
             _marker: PhantomData,
         }
     }
@@ -90,6 +107,14 @@ impl<'a> Context<'a> {
     pub(crate) fn reset_cursor(&mut self, cursor: Cursor) {
         self.cursor = cursor;
     }
+
+    /// Calls the function with the current cursor position as the mount point.
+    pub(crate) fn with_mount_point<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let mount_pos = std::mem::replace(&mut self.mount_pos, self.cursor.pos);
+        let result = f(self);
+        self.mount_pos = mount_pos;
+        result
+    }
 }
 
 #[cfg(test)]
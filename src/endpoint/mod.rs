@@ -10,9 +10,11 @@ mod and;
 mod apply_fn;
 mod fixed;
 mod lazy;
+mod mount;
 mod or;
 mod or_strict;
 mod reject;
+mod router;
 mod unit;
 mod value;
 
@@ -28,11 +30,13 @@ pub use self::and::And;
 pub use self::fixed::Fixed;
 pub use self::or::Or;
 pub use self::or_strict::OrStrict;
+pub use self::router::Router;
 
 pub use self::apply_fn::{apply_fn, ApplyFn};
 #[allow(deprecated)]
 #[doc(hidden)]
 pub use self::lazy::{lazy, Lazy};
+pub use self::mount::{mount, mount_point, Mount, MountPoint, MountPointEndpoint};
 #[allow(deprecated)]
 #[doc(hidden)]
 pub use self::reject::{reject, Reject};
@@ -0,0 +1,133 @@
+use std::fmt;
+
+use futures_util::future::{ready, Ready};
+use percent_encoding::percent_encode;
+
+use crate::endpoint::syntax::SEGMENT_ENCODE_SET;
+use crate::endpoint::{Context, Endpoint, EndpointError, EndpointResult, IntoEndpoint};
+use crate::error::Error;
+
+/// Create an endpoint which mounts the provided endpoint under the path prefix.
+///
+/// The segments of prefix are consumed before applying the inner endpoint,
+/// so the inner endpoint only sees the remaining path. The consumed prefix
+/// is available from the inner endpoint via `Context::mount_point()` or the
+/// endpoint `mount_point()`, which is useful for generating the URLs without
+/// knowing where it is mounted.
+///
+/// # Example
+///
+/// ```
+/// # use finchers::prelude::*;
+/// # use finchers::path;
+/// let v1 = path!(@get / "posts" / u32 /)
+///     .map(|id: u32| format!("v1: post {}", id));
+///
+/// let v2 = path!(@get / "posts" / u32 /)
+///     .map(|id: u32| format!("v2: post {}", id));
+///
+/// let endpoint = endpoint::mount("api/v1", v1)
+///     .or(endpoint::mount("api/v2", v2));
+/// # drop(endpoint);
+/// ```
+pub fn mount<'a, E>(prefix: &str, endpoint: E) -> Mount<E::Endpoint>
+where
+    E: IntoEndpoint<'a>,
+{
+    let segments = prefix
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| percent_encode(s.as_bytes(), SEGMENT_ENCODE_SET).to_string())
+        .collect();
+    (Mount {
+        segments,
+        endpoint: endpoint.into_endpoint(),
+    }).with_output::<E::Output>()
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Clone)]
+pub struct Mount<E> {
+    segments: Vec<String>,
+    endpoint: E,
+}
+
+impl<'a, E> Endpoint<'a> for Mount<E>
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Future = E::Future;
+
+    fn apply(&'a self, ecx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        for segment in &self.segments {
+            match ecx.next_segment() {
+                Some(s) if s == *segment => {}
+                _ => return Err(EndpointError::not_matched()),
+            }
+        }
+
+        ecx.with_mount_point(|ecx| self.endpoint.apply(ecx))
+    }
+}
+
+/// Create an endpoint which extracts the path prefix where the current endpoint is mounted.
+///
+/// # Example
+///
+/// ```
+/// # use finchers::prelude::*;
+/// # use finchers::{named_path, path};
+/// use finchers::endpoint::MountPoint;
+///
+/// let post = named_path!("post", @get / "posts" / u32 /);
+///
+/// let create = path!(@post / "posts" /)
+///     .and(endpoint::mount_point())
+///     .map(move |mount_point: MountPoint| {
+///         // e.g. "/api/v1/posts/42"
+///         post.mounted_url(&mount_point, &[&42]).unwrap()
+///     });
+///
+/// let endpoint = endpoint::mount("api/v1", create);
+/// # drop(endpoint);
+/// ```
+#[inline]
+pub fn mount_point() -> MountPointEndpoint {
+    (MountPointEndpoint { _priv: () }).with_output::<(MountPoint,)>()
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone)]
+pub struct MountPointEndpoint {
+    _priv: (),
+}
+
+impl<'a> Endpoint<'a> for MountPointEndpoint {
+    type Output = (MountPoint,);
+    type Future = Ready<Result<Self::Output, Error>>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let mount_point = MountPoint(cx.mount_point().to_owned());
+        Ok(ready(Ok((mount_point,))))
+    }
+}
+
+/// The path prefix where an endpoint is mounted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MountPoint(String);
+
+impl MountPoint {
+    /// Returns the percent-encoded prefix, without the trailing slash.
+    ///
+    /// The returned value is empty if the endpoint is not mounted.
+    pub fn as_str(&self) -> &str {
+        &*self.0
+    }
+}
+
+impl fmt::Display for MountPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&*self.0)
+    }
+}
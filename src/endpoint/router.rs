@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str;
+
+use futures_core::future::FutureObj;
+use percent_encoding::percent_encode;
+
+use crate::common::Tuple;
+use crate::endpoint::context::Cursor;
+use crate::endpoint::syntax::SEGMENT_ENCODE_SET;
+use crate::endpoint::{
+    Context, Endpoint, EndpointError, EndpointObj, EndpointResult, IsSendEndpoint,
+};
+use crate::error::Error;
+use crate::input::EncodedStr;
+
+/// An endpoint which dispatches the requests to the routes associated with
+/// the static path prefixes.
+///
+/// The prefixes are compiled into a tree of path segments, so that the
+/// candidate routes are found without trying every route in order as the
+/// long chain of `or` does. All routes must have the same output type.
+///
+/// The routes registered with the longer prefix are tried first, and the
+/// routes with the same prefix are tried in the order of registration.
+/// If no route matches, the errors are merged in the same way as `or`,
+/// i.e. the request is rejected with `405 Method Not Allowed` if a route
+/// matched the path but not the method.
+///
+/// # Example
+///
+/// ```
+/// # use finchers::prelude::*;
+/// # use finchers::path;
+/// use finchers::endpoint::Router;
+///
+/// let endpoint = Router::new()
+///     .route("/posts", path!(@get / u32 /).map(|id: u32| format!("post {}", id)))
+///     .route("/users", path!(@get / u32 /).map(|id: u32| format!("user {}", id)));
+/// # drop(endpoint);
+/// ```
+pub struct Router<T: Tuple + 'static> {
+    root: Node<T>,
+}
+
+struct Node<T: Tuple + 'static> {
+    children: HashMap<String, Node<T>>,
+    routes: Vec<EndpointObj<T>>,
+}
+
+impl<T: Tuple + 'static> Node<T> {
+    fn new() -> Node<T> {
+        Node {
+            children: HashMap::new(),
+            routes: vec![],
+        }
+    }
+
+    fn insert(&mut self, mut segments: impl Iterator<Item = String>, route: EndpointObj<T>) {
+        match segments.next() {
+            Some(segment) => self
+                .children
+                .entry(segment)
+                .or_insert_with(Node::new)
+                .insert(segments, route),
+            None => self.routes.push(route),
+        }
+    }
+
+    fn child(&self, segment: &EncodedStr) -> Option<&Node<T>> {
+        str::from_utf8(segment.as_bytes())
+            .ok()
+            .and_then(|segment| self.children.get(segment))
+    }
+}
+
+impl<T: Tuple + 'static> Default for Router<T> {
+    fn default() -> Router<T> {
+        Router::new()
+    }
+}
+
+impl<T: Tuple + 'static> fmt::Debug for Router<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.debug_struct("Router").finish()
+    }
+}
+
+impl<T: Tuple + 'static> Router<T> {
+    /// Creates an empty `Router`.
+    pub fn new() -> Router<T> {
+        Router { root: Node::new() }
+    }
+
+    /// Registers a route with the specified path prefix.
+    ///
+    /// The endpoint is applied to the remaining path after the prefix, and
+    /// the prefix is used as the mount point as `endpoint::mount()`.
+    pub fn route<E>(mut self, prefix: &str, endpoint: E) -> Router<T>
+    where
+        for<'a> E: IsSendEndpoint<'a, Output = T> + Send + Sync + 'static,
+    {
+        let segments = prefix
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(|s| percent_encode(s.as_bytes(), SEGMENT_ENCODE_SET).to_string());
+        self.root.insert(segments, EndpointObj::new(endpoint));
+        self
+    }
+}
+
+impl<'e, T: Tuple + 'static> Endpoint<'e> for Router<T> {
+    type Output = T;
+    type Future = FutureObj<'e, Result<T, Error>>;
+
+    fn apply(&'e self, ecx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        // Collect the nodes along the request path, with the cursor positions after their prefix.
+        let mut candidates: Vec<(&'e Node<T>, Cursor)> = vec![(&self.root, ecx.current_cursor())];
+        {
+            let mut ecx = ecx.clone_reborrowed();
+            let mut node = &self.root;
+            while let Some(child) = ecx.next_segment().and_then(|s| node.child(s)) {
+                node = child;
+                candidates.push((node, ecx.current_cursor()));
+            }
+        }
+
+        let mut error: Option<EndpointError> = None;
+        for (node, cursor) in candidates.into_iter().rev() {
+            for route in &node.routes {
+                ecx.reset_cursor(cursor.clone());
+                match ecx.with_mount_point(|ecx| route.apply(ecx)) {
+                    Ok(future) => return Ok(future),
+                    Err(err) => {
+                        error = Some(match error.take() {
+                            Some(error) => error.merge(err),
+                            None => err,
+                        })
+                    }
+                }
+            }
+        }
+
+        Err(error.unwrap_or_else(EndpointError::not_matched))
+    }
+}
+
+/// A helper macro for creating a `Router` from the pairs of path prefix and endpoint.
+///
+/// # Example
+///
+/// ```
+/// # use finchers::prelude::*;
+/// # use finchers::path;
+/// use finchers::router;
+///
+/// let endpoint = router! {
+///     "/posts" => path!(@get / u32 /).map(|id: u32| format!("post {}", id)),
+///     "/users" => path!(@get / u32 /).map(|id: u32| format!("user {}", id)),
+/// };
+/// # drop(endpoint);
+/// ```
+#[macro_export]
+macro_rules! router {
+    ($($prefix:expr => $e:expr),* $(,)*) => {
+        $crate::endpoint::Router::new()
+            $( .route($prefix, $e) )*
+    };
+}
@@ -0,0 +1,233 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+
+use regex::Regex;
+
+use super::Extracted;
+use crate::endpoint::{Context, Endpoint, EndpointError, EndpointResult};
+use crate::input::FromEncodedStr;
+
+/// Create an endpoint which parses a path segment into the specified type,
+/// if the percent-decoded segment matches the regular expression.
+///
+/// Unlike `param()`, the segments which do not match to the pattern or fail
+/// to parse are treated as "not matched", so that the subsequent routes are tried.
+/// Use `^` and `$` in the pattern to match against the whole segment.
+///
+/// # Panics
+///
+/// This function panics if the pattern is invalid.
+///
+/// # Example
+///
+/// ```
+/// # use finchers::prelude::*;
+/// # use finchers::path;
+/// use finchers::endpoint::syntax::param_matching;
+///
+/// let endpoint = path!(@get / "posts" / { param_matching::<String>(r"^[a-z0-9-]+$") } /)
+///     .map(|slug: String| format!("slug = {}", slug));
+/// # drop(endpoint);
+/// ```
+pub fn param_matching<T>(pattern: &str) -> ParamMatching<T>
+where
+    T: FromEncodedStr,
+{
+    let regex = Regex::new(pattern).unwrap_or_else(|err| panic!("invalid pattern: {}", err));
+    (ParamMatching {
+        regex,
+        _marker: PhantomData,
+    }).with_output::<(T,)>()
+}
+
+#[allow(missing_docs)]
+pub struct ParamMatching<T> {
+    regex: Regex,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for ParamMatching<T> {
+    fn clone(&self) -> Self {
+        ParamMatching {
+            regex: self.regex.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for ParamMatching<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParamMatching")
+            .field("regex", &self.regex)
+            .finish()
+    }
+}
+
+impl<'a, T> Endpoint<'a> for ParamMatching<T>
+where
+    T: FromEncodedStr,
+{
+    type Output = (T,);
+    type Future = Extracted<T>;
+
+    fn apply(&self, ecx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let s = ecx.next_segment().ok_or_else(EndpointError::not_matched)?;
+        let matched = s
+            .percent_decode()
+            .map(|decoded| self.regex.is_match(&decoded))
+            .unwrap_or(false);
+        if !matched {
+            return Err(EndpointError::not_matched());
+        }
+        let x = T::from_encoded_str(s).map_err(|_| EndpointError::not_matched())?;
+        Ok(Extracted(Some(x)))
+    }
+}
+
+/// Create an endpoint which parses a path segment into the specified type,
+/// if the parsed value is within the range.
+///
+/// The segments which fail to parse or are out of the range are treated as "not matched".
+///
+/// # Example
+///
+/// ```
+/// # use finchers::prelude::*;
+/// # use finchers::path;
+/// use finchers::endpoint::syntax::param_in_range;
+///
+/// let endpoint = path!(@get / "years" / { param_in_range(1970u32..=2100) } /)
+///     .map(|year: u32| format!("year = {}", year));
+/// # drop(endpoint);
+/// ```
+pub fn param_in_range<T>(range: impl RangeBounds<T>) -> ParamInRange<T>
+where
+    T: FromEncodedStr + PartialOrd + Clone,
+{
+    fn cloned<T: Clone>(bound: Bound<&T>) -> Bound<T> {
+        match bound {
+            Bound::Included(x) => Bound::Included(x.clone()),
+            Bound::Excluded(x) => Bound::Excluded(x.clone()),
+            Bound::Unbounded => Bound::Unbounded,
+        }
+    }
+
+    (ParamInRange {
+        start: cloned(range.start_bound()),
+        end: cloned(range.end_bound()),
+    }).with_output::<(T,)>()
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Clone)]
+pub struct ParamInRange<T> {
+    start: Bound<T>,
+    end: Bound<T>,
+}
+
+impl<T: PartialOrd> ParamInRange<T> {
+    fn contains(&self, x: &T) -> bool {
+        let after_start = match self.start {
+            Bound::Included(ref start) => x >= start,
+            Bound::Excluded(ref start) => x > start,
+            Bound::Unbounded => true,
+        };
+        let before_end = match self.end {
+            Bound::Included(ref end) => x <= end,
+            Bound::Excluded(ref end) => x < end,
+            Bound::Unbounded => true,
+        };
+        after_start && before_end
+    }
+}
+
+impl<'a, T> Endpoint<'a> for ParamInRange<T>
+where
+    T: FromEncodedStr + PartialOrd,
+{
+    type Output = (T,);
+    type Future = Extracted<T>;
+
+    fn apply(&self, ecx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let s = ecx.next_segment().ok_or_else(EndpointError::not_matched)?;
+        match T::from_encoded_str(s) {
+            Ok(ref x) if !self.contains(x) => Err(EndpointError::not_matched()),
+            Ok(x) => Ok(Extracted(Some(x))),
+            Err(..) => Err(EndpointError::not_matched()),
+        }
+    }
+}
+
+/// Create an endpoint which parses a path segment into the specified type,
+/// if the parsed value is equal to one of the candidates.
+///
+/// The segments which fail to parse or are not in the candidates are treated as "not matched".
+///
+/// # Example
+///
+/// ```
+/// # use finchers::prelude::*;
+/// # use finchers::path;
+/// use finchers::endpoint::syntax::param_one_of;
+///
+/// let order = param_one_of(vec!["newest".to_owned(), "popular".to_owned()]);
+///
+/// let endpoint = path!(@get / "posts" / { order } /)
+///     .map(|order: String| format!("order = {}", order));
+/// # drop(endpoint);
+/// ```
+pub fn param_one_of<T>(candidates: impl IntoIterator<Item = T>) -> ParamOneOf<T>
+where
+    T: FromEncodedStr + PartialEq,
+{
+    (ParamOneOf {
+        candidates: candidates.into_iter().collect(),
+    }).with_output::<(T,)>()
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Clone)]
+pub struct ParamOneOf<T> {
+    candidates: Vec<T>,
+}
+
+impl<'a, T> Endpoint<'a> for ParamOneOf<T>
+where
+    T: FromEncodedStr + PartialEq,
+{
+    type Output = (T,);
+    type Future = Extracted<T>;
+
+    fn apply(&self, ecx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let s = ecx.next_segment().ok_or_else(EndpointError::not_matched)?;
+        match T::from_encoded_str(s) {
+            Ok(x) => {
+                if self.candidates.contains(&x) {
+                    Ok(Extracted(Some(x)))
+                } else {
+                    Err(EndpointError::not_matched())
+                }
+            }
+            Err(..) => Err(EndpointError::not_matched()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_contains() {
+        let range = param_in_range(1u32..10);
+        assert!(!range.contains(&0));
+        assert!(range.contains(&1));
+        assert!(range.contains(&9));
+        assert!(!range.contains(&10));
+
+        let range = param_in_range(5u32..);
+        assert!(!range.contains(&4));
+        assert!(range.contains(&std::u32::MAX));
+    }
+}
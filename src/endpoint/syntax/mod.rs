@@ -3,6 +3,9 @@
 #[allow(missing_docs)]
 pub mod verb;
 
+mod matching;
+mod template;
+
 use std::borrow::Cow;
 use std::fmt;
 use std::marker::PhantomData;
@@ -19,6 +22,11 @@ use crate::error;
 use crate::error::Error;
 use crate::input::FromEncodedStr;
 
+pub use self::matching::{
+    param_in_range, param_matching, param_one_of, ParamInRange, ParamMatching, ParamOneOf,
+};
+pub use self::template::{NamedPath, PathTemplate, TemplateSegment, UrlError};
+
 #[doc(hidden)]
 #[derive(Debug)]
 #[must_use = "futures does not anything unless polled."]
@@ -61,6 +69,12 @@ define_encode_set! {
     pub SEGMENT_ENCODE_SET = [DEFAULT_ENCODE_SET] | {'/'}
 }
 
+define_encode_set! {
+    /// The encode set for the values of parameters, which are decoded on extraction
+    #[doc(hidden)]
+    pub PARAM_ENCODE_SET = [SEGMENT_ENCODE_SET] | {'%'}
+}
+
 /// Create an endpoint which validates a path segment.
 ///
 /// It takes a path segment from the context and check if it is equal
@@ -252,6 +266,9 @@ where
 
 /// A helper macro for creating an endpoint which matches to the specified HTTP path.
 ///
+/// Each segment is a literal, a type extracted with `param()`, or an endpoint
+/// enclosed in braces such as `{ param_matching::<String>(r"^[a-z]+$") }`.
+///
 /// # Example
 ///
 /// The following macro call
@@ -305,3 +322,66 @@ macro_rules! path_impl {
     (@segment $t:ty) => ( $crate::endpoint::syntax::param::<$t>() );
     (@segment $s:expr) => ( ($s) );
 }
+
+/// A helper macro for creating an endpoint associated with a name and a template of its path.
+///
+/// The syntax after the name is the same as `path!()`. The returned endpoint can
+/// generate the URLs with parameters, which are kept in sync with the routing.
+///
+/// Note that the segments given as expressions are evaluated twice, once for
+/// the endpoint and once for the template.
+///
+/// # Example
+///
+/// ```
+/// # use finchers::prelude::*;
+/// # use finchers::named_path;
+/// let post = named_path!("post", @get / "posts" / u32 /);
+///
+/// assert_eq!(post.name(), "post");
+/// assert_eq!(post.template().to_string(), "/posts/{}");
+/// assert_eq!(post.url(&[&42]).unwrap(), "/posts/42");
+///
+/// let endpoint = post.map(|id: u32| format!("post {}", id));
+/// # drop(endpoint);
+/// ```
+#[macro_export]
+macro_rules! named_path {
+    // with method
+    ($name:expr, @$method:ident $($t:tt)*) => (
+        $crate::endpoint::syntax::NamedPath::new(
+            $name,
+            $crate::endpoint::IntoEndpoint::into_endpoint($crate::path!(@$method $($t)*)),
+            $crate::path_template_impl!(@start $($t)*),
+        )
+    );
+
+    // without method
+    ($name:expr, / $($t:tt)*) => (
+        $crate::endpoint::syntax::NamedPath::new(
+            $name,
+            $crate::endpoint::IntoEndpoint::into_endpoint($crate::path!(/ $($t)*)),
+            $crate::path_template_impl!(@start / $($t)*),
+        )
+    );
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! path_template_impl {
+    (@start / $head:tt $(/ $tail:tt)*) => {{
+        let __t = $crate::endpoint::syntax::PathTemplate::new();
+        let __t = $crate::path_template_impl!(@push __t, $head);
+        $(
+            let __t = $crate::path_template_impl!(@push __t, $tail);
+        )*
+        __t
+    }};
+    (@start / $head:tt $(/ $tail:tt)* /) => {
+        $crate::path_template_impl!(@start / $head $(/ $tail)*)
+    };
+    (@start /) => ( $crate::endpoint::syntax::PathTemplate::new() );
+
+    (@push $p:ident, $t:ty) => ( $p.param() );
+    (@push $p:ident, $s:expr) => ( $p.push($s) );
+}
@@ -0,0 +1,267 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use http::StatusCode;
+use percent_encoding::percent_encode;
+
+use super::{Param, ParamInRange, ParamMatching, ParamOneOf, PARAM_ENCODE_SET, SEGMENT_ENCODE_SET};
+use crate::endpoint::{Context, Endpoint, EndpointResult, MountPoint};
+use crate::error::HttpError;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Param,
+}
+
+/// A template of HTTP paths, used for generating the URLs of a route.
+///
+/// The value of this type is usually created by `named_path!()`, with the same
+/// segments as the corresponding endpoint.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathTemplate {
+    segments: Vec<Segment>,
+}
+
+impl PathTemplate {
+    /// Creates an empty `PathTemplate`, which represents the root path.
+    pub fn new() -> PathTemplate {
+        PathTemplate::default()
+    }
+
+    /// Appends a fixed segment to this template.
+    pub fn segment(mut self, s: impl AsRef<str>) -> PathTemplate {
+        let encoded = percent_encode(s.as_ref().as_bytes(), SEGMENT_ENCODE_SET).to_string();
+        self.segments.push(Segment::Literal(encoded));
+        self
+    }
+
+    /// Appends a placeholder of parameter to this template.
+    pub fn param(mut self) -> PathTemplate {
+        self.segments.push(Segment::Param);
+        self
+    }
+
+    #[doc(hidden)]
+    pub fn push(self, segment: impl TemplateSegment) -> PathTemplate {
+        segment.push_to(self)
+    }
+
+    /// Returns the number of parameters required to generate a URL.
+    pub fn num_params(&self) -> usize {
+        self.segments
+            .iter()
+            .filter(|segment| **segment == Segment::Param)
+            .count()
+    }
+
+    /// Generates a URL by filling the parameters with the specified values.
+    ///
+    /// The formatted values are percent-encoded so that they are extracted
+    /// as a single segment with the same value.
+    pub fn url(&self, params: &[&dyn fmt::Display]) -> Result<String, UrlError> {
+        if params.len() != self.num_params() {
+            return Err(UrlError {
+                expected: self.num_params(),
+                actual: params.len(),
+            });
+        }
+
+        let mut params = params.iter();
+        let mut url = String::new();
+        for segment in &self.segments {
+            url.push('/');
+            match segment {
+                Segment::Literal(s) => url.push_str(s),
+                Segment::Param => {
+                    let param = params.next().expect("the number of params is checked");
+                    let param = param.to_string();
+                    url.extend(percent_encode(param.as_bytes(), PARAM_ENCODE_SET));
+                }
+            }
+        }
+        if url.is_empty() {
+            url.push('/');
+        }
+        Ok(url)
+    }
+}
+
+impl fmt::Display for PathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.segments.is_empty() {
+            return f.write_str("/");
+        }
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(s) => write!(f, "/{}", s)?,
+                Segment::Param => f.write_str("/{}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A trait representing the values which can be used as a segment of `PathTemplate`.
+#[doc(hidden)]
+#[allow(missing_docs)]
+pub trait TemplateSegment {
+    fn push_to(self, template: PathTemplate) -> PathTemplate;
+}
+
+impl<'s> TemplateSegment for &'s str {
+    fn push_to(self, template: PathTemplate) -> PathTemplate {
+        template.segment(self)
+    }
+}
+
+impl TemplateSegment for String {
+    fn push_to(self, template: PathTemplate) -> PathTemplate {
+        template.segment(self)
+    }
+}
+
+impl<'s> TemplateSegment for Cow<'s, str> {
+    fn push_to(self, template: PathTemplate) -> PathTemplate {
+        template.segment(self)
+    }
+}
+
+macro_rules! impl_template_segment_for_params {
+    ($($T:ident,)*) => {$(
+        impl<T> TemplateSegment for $T<T> {
+            fn push_to(self, template: PathTemplate) -> PathTemplate {
+                template.param()
+            }
+        }
+    )*};
+}
+
+impl_template_segment_for_params! {
+    Param,
+    ParamInRange,
+    ParamMatching,
+    ParamOneOf,
+}
+
+/// An endpoint associated with a name and a template of its path.
+///
+/// The value of this type is created by `named_path!()`.
+#[derive(Debug, Clone)]
+pub struct NamedPath<E> {
+    name: Cow<'static, str>,
+    endpoint: E,
+    template: PathTemplate,
+}
+
+impl<E> NamedPath<E> {
+    #[doc(hidden)]
+    pub fn new(
+        name: impl Into<Cow<'static, str>>,
+        endpoint: E,
+        template: PathTemplate,
+    ) -> NamedPath<E> {
+        NamedPath {
+            name: name.into(),
+            endpoint,
+            template,
+        }
+    }
+
+    /// Returns the name of this route.
+    pub fn name(&self) -> &str {
+        &*self.name
+    }
+
+    /// Returns the template of the path matched by this endpoint.
+    pub fn template(&self) -> &PathTemplate {
+        &self.template
+    }
+
+    /// Generates a URL of this route with the specified parameters.
+    pub fn url(&self, params: &[&dyn fmt::Display]) -> Result<String, UrlError> {
+        self.template.url(params)
+    }
+
+    /// Generates a URL of this route under the specified mount point.
+    pub fn mounted_url(
+        &self,
+        mount_point: &MountPoint,
+        params: &[&dyn fmt::Display],
+    ) -> Result<String, UrlError> {
+        self.url(params)
+            .map(|url| format!("{}{}", mount_point.as_str(), url))
+    }
+}
+
+impl<'a, E: Endpoint<'a>> Endpoint<'a> for NamedPath<E> {
+    type Output = E::Output;
+    type Future = E::Future;
+
+    #[inline]
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        self.endpoint.apply(cx)
+    }
+}
+
+/// An error which will be returned when the number of parameters is mismatched
+/// at generating a URL.
+#[derive(Debug)]
+pub struct UrlError {
+    expected: usize,
+    actual: usize,
+}
+
+impl fmt::Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the URL requires {} parameter(s), but {} given",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl HttpError for UrlError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url() {
+        let template = PathTemplate::new()
+            .segment("api")
+            .segment("user posts")
+            .param();
+        assert_eq!(template.num_params(), 1);
+        assert_eq!(template.to_string(), "/api/user%20posts/{}");
+        assert_eq!(
+            template.url(&[&"a/b c"]).unwrap(),
+            "/api/user%20posts/a%2Fb%20c"
+        );
+        assert!(template.url(&[]).is_err());
+    }
+
+    #[test]
+    fn test_url_round_trip() {
+        use crate::input::{EncodedStr, FromEncodedStr};
+
+        let template = PathTemplate::new().param();
+        for &param in &["a%2Fb", "100%", "%%20", "a/b c"] {
+            let url = template.url(&[&param]).unwrap();
+            let segment = unsafe { EncodedStr::new_unchecked(&url[1..]) };
+            assert_eq!(String::from_encoded_str(segment).unwrap(), param);
+        }
+        assert_eq!(template.url(&[&"100%"]).unwrap(), "/100%25");
+    }
+
+    #[test]
+    fn test_url_root() {
+        assert_eq!(PathTemplate::new().url(&[]).unwrap(), "/");
+    }
+}
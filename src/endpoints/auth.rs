@@ -0,0 +1,349 @@
+//! Endpoints for authenticating the client with the `Authorization` header.
+//!
+//! # Example
+//!
+//! ```
+//! # use finchers::prelude::*;
+//! # use finchers::path;
+//! use finchers::endpoints::auth;
+//! use finchers::endpoints::auth::Basic;
+//! use futures_util::future::ready;
+//!
+//! #[derive(Debug)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! let admin = path!(@get / "admin")
+//!     .and(auth::basic("admin area", |credentials: Basic| {
+//!         // Check the credentials with the database, etc...
+//!         let user = if credentials.password.as_ref().map(|s| s.as_str()) == Some("secret") {
+//!             Some(User { name: credentials.username })
+//!         } else {
+//!             None
+//!         };
+//!         ready(Ok(user))
+//!     }))
+//!     .map(|user: User| format!("Hello, {}", user.name));
+//! # drop(admin);
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::PinMut;
+
+use futures_core::future::{Future, TryFuture};
+use futures_core::task;
+use futures_core::task::Poll;
+use pin_utils::unsafe_pinned;
+
+use http::header::{HeaderMap, HeaderValue};
+use http::{header, StatusCode};
+use hyperx::header::{Authorization, Header, Scheme};
+
+use crate::endpoint::{Context, Endpoint, EndpointError, EndpointResult};
+use crate::error::{Error, HttpError};
+
+pub use hyperx::header::{Basic, Bearer};
+
+// ==== Challenge ====
+
+/// A challenge sent to the client with the `WWW-Authenticate` header.
+#[derive(Debug, Clone)]
+pub struct Challenge {
+    scheme: Cow<'static, str>,
+    params: Vec<(Cow<'static, str>, String)>,
+}
+
+impl Challenge {
+    /// Create a new `Challenge` with the specified authentication scheme and realm.
+    pub fn new(scheme: impl Into<Cow<'static, str>>, realm: impl Into<String>) -> Challenge {
+        Challenge {
+            scheme: scheme.into(),
+            params: vec![("realm".into(), realm.into())],
+        }
+    }
+
+    /// Create a new `Challenge` without the realm parameter.
+    pub fn bare(scheme: impl Into<Cow<'static, str>>) -> Challenge {
+        Challenge {
+            scheme: scheme.into(),
+            params: vec![],
+        }
+    }
+
+    /// Appends an auth-param to this challenge.
+    pub fn param(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        value: impl Into<String>,
+    ) -> Challenge {
+        self.params.push((name.into(), value.into()));
+        self
+    }
+}
+
+impl fmt::Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.scheme)?;
+        for (i, (name, value)) in self.params.iter().enumerate() {
+            f.write_str(if i == 0 { " " } else { ", " })?;
+            write!(f, "{}=\"", name)?;
+            for c in value.chars() {
+                if c == '"' || c == '\\' {
+                    f.write_str("\\")?;
+                }
+                write!(f, "{}", c)?;
+            }
+            f.write_str("\"")?;
+        }
+        Ok(())
+    }
+}
+
+/// An error which represents that the client is not authenticated.
+///
+/// This error will be converted into a `401 Unauthorized` response
+/// with the associated challenge.
+#[derive(Debug)]
+pub struct Unauthorized {
+    challenge: Challenge,
+    reason: Cow<'static, str>,
+}
+
+impl Unauthorized {
+    /// Create a new `Unauthorized` with the specified challenge and reason.
+    pub fn new(challenge: Challenge, reason: impl Into<Cow<'static, str>>) -> Unauthorized {
+        Unauthorized {
+            challenge,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for Unauthorized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+impl HttpError for Unauthorized {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNAUTHORIZED
+    }
+
+    fn headers(&self, headers: &mut HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(&self.challenge.to_string()) {
+            headers.insert(header::WWW_AUTHENTICATE, value);
+        }
+    }
+}
+
+// ==== Auth ====
+
+/// Create an endpoint which authenticates the client with the Basic authentication scheme.
+///
+/// The provided function receives the credentials and returns a future which
+/// resolves to the authenticated principal, or `None` if the credentials are invalid.
+pub fn basic<F, R, P>(realm: impl Into<String>, verifier: F) -> Auth<Basic, F>
+where
+    F: Fn(Basic) -> R,
+    R: TryFuture<Ok = Option<P>, Error = Error>,
+{
+    custom(Challenge::new("Basic", realm), verifier)
+}
+
+/// Create an endpoint which authenticates the client with the Bearer authentication scheme.
+///
+/// The provided function receives the token and returns a future which
+/// resolves to the authenticated principal, or `None` if the token is invalid.
+pub fn bearer<F, R, P>(realm: impl Into<String>, verifier: F) -> Auth<Bearer, F>
+where
+    F: Fn(Bearer) -> R,
+    R: TryFuture<Ok = Option<P>, Error = Error>,
+{
+    custom(Challenge::new("Bearer", realm), verifier)
+}
+
+/// Create an endpoint which authenticates the client with a custom authentication scheme.
+pub fn custom<S, F, R, P>(challenge: Challenge, verifier: F) -> Auth<S, F>
+where
+    S: Scheme + 'static,
+    F: Fn(S) -> R,
+    R: TryFuture<Ok = Option<P>, Error = Error>,
+{
+    Auth {
+        challenge,
+        verifier,
+        _marker: PhantomData,
+    }
+}
+
+#[allow(missing_docs)]
+pub struct Auth<S, F> {
+    challenge: Challenge,
+    verifier: F,
+    _marker: PhantomData<fn() -> S>,
+}
+
+impl<S, F> fmt::Debug for Auth<S, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Auth")
+            .field("challenge", &self.challenge)
+            .finish()
+    }
+}
+
+impl<S, F> Auth<S, F> {
+    /// Converts this endpoint into the one which also accepts the unauthenticated requests.
+    ///
+    /// The returned endpoint outputs `None` if the request does not have the `Authorization`
+    /// header. The requests with invalid credentials are still rejected.
+    pub fn optional(self) -> OptionalAuth<S, F> {
+        OptionalAuth { auth: self }
+    }
+
+    fn credentials(&self, cx: &mut Context<'_>) -> Result<Option<S>, Error>
+    where
+        S: Scheme + 'static,
+    {
+        match cx.input().headers().get(header::AUTHORIZATION) {
+            Some(h) => Authorization::<S>::parse_header(&h.as_bytes().into())
+                .map(|Authorization(credentials)| Some(credentials))
+                .map_err(|_| self.unauthorized("invalid credentials").into()),
+            None => Ok(None),
+        }
+    }
+
+    fn unauthorized(&self, reason: &'static str) -> Unauthorized {
+        Unauthorized::new(self.challenge.clone(), reason)
+    }
+}
+
+impl<'a, S, F, R, P> Endpoint<'a> for Auth<S, F>
+where
+    S: Scheme + 'static,
+    F: Fn(S) -> R + 'a,
+    R: TryFuture<Ok = Option<P>, Error = Error> + 'a,
+{
+    type Output = (P,);
+    type Future = AuthFuture<'a, R>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        match self.credentials(cx).map_err(EndpointError::custom)? {
+            Some(credentials) => Ok(AuthFuture {
+                future: Some((self.verifier)(credentials)),
+                challenge: &self.challenge,
+            }),
+            None => Err(EndpointError::custom(
+                self.unauthorized("missing credentials"),
+            )),
+        }
+    }
+}
+
+/// An endpoint which authenticates the client if the credentials are provided.
+#[derive(Debug)]
+pub struct OptionalAuth<S, F> {
+    auth: Auth<S, F>,
+}
+
+impl<'a, S, F, R, P> Endpoint<'a> for OptionalAuth<S, F>
+where
+    S: Scheme + 'static,
+    F: Fn(S) -> R + 'a,
+    R: TryFuture<Ok = Option<P>, Error = Error> + 'a,
+{
+    type Output = (Option<P>,);
+    type Future = OptionalAuthFuture<'a, R>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let credentials = self.auth.credentials(cx).map_err(EndpointError::custom)?;
+        Ok(OptionalAuthFuture {
+            inner: AuthFuture {
+                future: credentials.map(|credentials| (self.auth.verifier)(credentials)),
+                challenge: &self.auth.challenge,
+            },
+        })
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct AuthFuture<'a, R> {
+    future: Option<R>,
+    challenge: &'a Challenge,
+}
+
+impl<'a, R, P> AuthFuture<'a, R>
+where
+    R: TryFuture<Ok = Option<P>, Error = Error>,
+{
+    fn poll_principal(
+        self: PinMut<'_, Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Result<Option<P>, Error>> {
+        let this = unsafe { PinMut::get_mut_unchecked(self) };
+        let future = match this.future {
+            Some(ref mut future) => unsafe { PinMut::new_unchecked(future) },
+            None => return Poll::Ready(Ok(None)),
+        };
+        let challenge = this.challenge;
+        future.try_poll(cx).map(|result| match result {
+            Ok(Some(principal)) => Ok(Some(principal)),
+            Ok(None) => Err(Unauthorized::new(challenge.clone(), "invalid credentials").into()),
+            Err(err) => Err(err),
+        })
+    }
+}
+
+impl<'a, R, P> Future for AuthFuture<'a, R>
+where
+    R: TryFuture<Ok = Option<P>, Error = Error>,
+{
+    type Output = Result<(P,), Error>;
+
+    fn poll(self: PinMut<'_, Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        self.poll_principal(cx).map(|result| {
+            result.map(|principal| (principal.expect("the credentials should be provided"),))
+        })
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct OptionalAuthFuture<'a, R> {
+    inner: AuthFuture<'a, R>,
+}
+
+impl<'a, R> OptionalAuthFuture<'a, R> {
+    unsafe_pinned!(inner: AuthFuture<'a, R>);
+}
+
+impl<'a, R, P> Future for OptionalAuthFuture<'a, R>
+where
+    R: TryFuture<Ok = Option<P>, Error = Error>,
+{
+    type Output = Result<(Option<P>,), Error>;
+
+    fn poll(mut self: PinMut<'_, Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        self.inner()
+            .poll_principal(cx)
+            .map(|result| result.map(|principal| (principal,)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_challenge() {
+        let challenge = Challenge::new("Bearer", "api \"v1\"").param("error", "invalid_token");
+        assert_eq!(
+            challenge.to_string(),
+            r#"Bearer realm="api \"v1\"", error="invalid_token""#
+        );
+    }
+}
@@ -0,0 +1,808 @@
+//! Components for the conditional requests and caching of responses.
+//!
+//! The wrapper created by `cache()` buffers the response body of `GET` and `HEAD`
+//! requests, attaches a strong `ETag` computed from its content and responds with
+//! `304 Not Modified` if the request has a matching `If-None-Match` header.
+//!
+//! If the in-process store is enabled with `Cache::lru()`, the responses which are
+//! permitted to be stored by `Cache-Control` (i.e. have a `max-age` or `s-maxage`
+//! directive and neither `no-store`, `no-cache` nor `private`) are kept for the specified
+//! period, and the subsequent requests are answered without running the inner endpoint.
+//! The stored responses are distinguished by the method, the URI and the request headers
+//! listed in `Vary`. As a shared cache, the requests with `Authorization` only use and
+//! populate the store if the response has `public`, `s-maxage` or `must-revalidate`.
+//!
+//! # Example
+//!
+//! ```
+//! # use finchers::prelude::*;
+//! # use finchers::path;
+//! use finchers::endpoints::cache::cache;
+//!
+//! // Only `ETag` and `If-None-Match`.
+//! let endpoint = path!(@get / "report")
+//!     .map(|| "expensive report")
+//!     .wrap(cache());
+//! # drop(endpoint);
+//!
+//! // The responses with `Cache-Control: max-age=...` are also stored.
+//! let endpoint = path!(@get / "report")
+//!     .map(|| "expensive report")
+//!     .wrap(cache().lru(128));
+//! # drop(endpoint);
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error as StdError;
+use std::pin::PinMut;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use bytes::{Buf, Bytes, BytesMut};
+use futures_core::future::{Future, TryFuture};
+use futures_core::task;
+use futures_core::task::Poll;
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use http::response::Parts;
+use http::{header, Method, Response, StatusCode};
+
+use crate::common::{poll_01_with_cx, Either};
+use crate::endpoint::wrapper::Wrapper;
+use crate::endpoint::{Context, Endpoint, EndpointResult};
+use crate::error::{err_msg, Error};
+use crate::input::{with_get_cx, Input};
+use crate::output::payload::{Once, Payload};
+use crate::output::{Output, OutputContext};
+
+/// The type of response bodies returned from the endpoints created by `Cache`.
+pub type CacheBody<Bd> = Either<Once<Bytes>, Bd>;
+
+/// Create a wrapper for creating an endpoint which attaches `ETag` to the responses
+/// and handles `If-None-Match`.
+pub fn cache() -> Cache {
+    Cache { store: None }
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Clone)]
+pub struct Cache {
+    store: Option<Arc<Store>>,
+}
+
+impl Cache {
+    /// Enables the in-process store which keeps up to `capacity` responses.
+    ///
+    /// When the store is full, the least recently used response is evicted.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `capacity` is zero.
+    pub fn lru(self, capacity: usize) -> Cache {
+        assert!(capacity > 0, "the capacity must be greater than zero");
+        Cache {
+            store: Some(Arc::new(Store {
+                capacity,
+                inner: Mutex::new(StoreInner::default()),
+            })),
+        }
+    }
+}
+
+impl<'a, E> Wrapper<'a, E> for Cache
+where
+    E: Endpoint<'a>,
+    E::Output: Output,
+{
+    type Output = (Response<CacheBody<<E::Output as Output>::Body>>,);
+    type Endpoint = CacheEndpoint<E>;
+
+    fn wrap(self, endpoint: E) -> Self::Endpoint {
+        CacheEndpoint {
+            endpoint,
+            cache: self,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct CacheEndpoint<E> {
+    endpoint: E,
+    cache: Cache,
+}
+
+impl<'a, E> Endpoint<'a> for CacheEndpoint<E>
+where
+    E: Endpoint<'a>,
+    E::Output: Output,
+{
+    type Output = (Response<CacheBody<<E::Output as Output>::Body>>,);
+    type Future = CacheFuture<'a, E::Future, <E::Output as Output>::Body>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        // The inner endpoint is always applied in order to check whether the request matches,
+        // but its future is not polled if the stored response is available.
+        let future = self.endpoint.apply(cx)?;
+
+        let input = cx.input();
+        let request = RequestInfo::new(&*input, self.cache.store.as_ref().map(|s| &**s));
+        let state = match request.lookup(&*input) {
+            Some((mut headers, body, age)) => {
+                headers.insert(header::AGE, HeaderValue::from(age));
+                CacheState::Ready(Some(conditional_response(
+                    headers,
+                    body,
+                    request.if_none_match.as_ref().map(|s| s.as_str()),
+                )))
+            }
+            None => CacheState::Pending(future),
+        };
+
+        Ok(CacheFuture { state, request })
+    }
+}
+
+/// The information of the request used after the inner future completes.
+#[derive(Debug)]
+struct RequestInfo<'a> {
+    store: Option<&'a Store>,
+    cacheable: bool,
+    authorized: bool,
+    no_store: bool,
+    no_cache: bool,
+    base_key: String,
+    if_none_match: Option<String>,
+}
+
+impl<'a> RequestInfo<'a> {
+    fn new(input: &Input, store: Option<&'a Store>) -> RequestInfo<'a> {
+        let directives = CacheControl::parse(input.headers());
+        RequestInfo {
+            store,
+            cacheable: *input.method() == Method::GET || *input.method() == Method::HEAD,
+            authorized: input.headers().contains_key(header::AUTHORIZATION),
+            no_store: directives.no_store,
+            no_cache: directives.no_cache,
+            base_key: format!("{} {}", input.method(), input.uri()),
+            if_none_match: input
+                .headers()
+                .get(header::IF_NONE_MATCH)
+                .and_then(|value| value.to_str().ok())
+                .map(ToOwned::to_owned),
+        }
+    }
+
+    fn lookup(&self, input: &Input) -> Option<(HeaderMap, Bytes, u64)> {
+        if !self.cacheable || self.no_cache || self.no_store {
+            return None;
+        }
+        self.store?
+            .lookup(&self.base_key, input.headers(), self.authorized)
+    }
+
+    fn store(&self, parts: &Parts, body: &Bytes) {
+        let store = match self.store {
+            Some(store) if !self.no_store => store,
+            _ => return,
+        };
+        let directives = CacheControl::parse(&parts.headers);
+        if directives.no_store || directives.no_cache || directives.private {
+            return;
+        }
+        // RFC 7234, Section 3.2
+        let shared = directives.public || directives.s_maxage || directives.must_revalidate;
+        if self.authorized && !shared {
+            return;
+        }
+        let max_age = match directives.max_age {
+            Some(max_age) if max_age > 0 => max_age,
+            _ => return,
+        };
+        if parts.headers.contains_key(header::SET_COOKIE) {
+            return;
+        }
+        let vary = match parse_vary(&parts.headers) {
+            Some(vary) => vary,
+            None => return,
+        };
+        with_get_cx(|input| {
+            store.insert(
+                &self.base_key,
+                vary,
+                input.headers(),
+                parts.headers.clone(),
+                body.clone(),
+                Duration::from_secs(max_age),
+                shared,
+            )
+        });
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct CacheFuture<'a, Fut, Bd> {
+    state: CacheState<Fut, Bd>,
+    request: RequestInfo<'a>,
+}
+
+#[derive(Debug)]
+enum CacheState<Fut, Bd> {
+    Pending(Fut),
+    Buffering {
+        parts: Option<Parts>,
+        body: Bd,
+        buf: BytesMut,
+    },
+    Ready(Option<Response<CacheBody<Bd>>>),
+}
+
+impl<'a, Fut, Bd> Future for CacheFuture<'a, Fut, Bd>
+where
+    Fut: TryFuture<Error = Error>,
+    Fut::Ok: Output<Body = Bd>,
+    Bd: Payload,
+{
+    type Output = Result<(Response<CacheBody<Bd>>,), Error>;
+
+    fn poll(self: PinMut<'_, Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = unsafe { PinMut::get_mut_unchecked(self) };
+        loop {
+            let next = match this.state {
+                CacheState::Pending(ref mut f) => {
+                    let f = unsafe { PinMut::new_unchecked(f) };
+                    let output = match f.try_poll(cx) {
+                        Poll::Ready(Ok(output)) => output,
+                        Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    let response = match with_get_cx(|input| {
+                        let mut ocx = OutputContext::new(input);
+                        output.respond(&mut ocx)
+                    }) {
+                        Ok(response) => response,
+                        Err(err) => return Poll::Ready(Err(err.into())),
+                    };
+                    if !this.request.cacheable || response.status() != StatusCode::OK {
+                        return Poll::Ready(Ok((response.map(Either::Right),)));
+                    }
+                    let (parts, body) = response.into_parts();
+                    CacheState::Buffering {
+                        parts: Some(parts),
+                        body,
+                        buf: BytesMut::new(),
+                    }
+                }
+                CacheState::Buffering {
+                    ref mut parts,
+                    ref mut body,
+                    ref mut buf,
+                } => {
+                    match poll_01_with_cx(cx, || body.poll_data()) {
+                        Poll::Ready(Ok(Some(data))) => {
+                            buf.extend_from_slice(&data.collect::<Bytes>());
+                            continue;
+                        }
+                        Poll::Ready(Ok(None)) => {}
+                        Poll::Ready(Err(err)) => {
+                            let err: Box<dyn StdError + Send + Sync> = err.into();
+                            return Poll::Ready(Err(err_msg(
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                err.to_string(),
+                            )));
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    }
+
+                    let mut parts = parts.take().expect("the future has already polled");
+                    let body = buf.take().freeze();
+                    if !parts.headers.contains_key(header::ETAG) {
+                        if let Ok(etag) = HeaderValue::from_str(&strong_etag(&body)) {
+                            parts.headers.insert(header::ETAG, etag);
+                        }
+                    }
+                    this.request.store(&parts, &body);
+
+                    CacheState::Ready(Some(conditional_response(
+                        parts.headers,
+                        body,
+                        this.request.if_none_match.as_ref().map(|s| s.as_str()),
+                    )))
+                }
+                CacheState::Ready(ref mut response) => {
+                    let response = response.take().expect("the future has already polled");
+                    return Poll::Ready(Ok((response,)));
+                }
+            };
+            this.state = next;
+        }
+    }
+}
+
+/// Creates the response from a buffered response, which may be `304 Not Modified`.
+fn conditional_response<Bd>(
+    headers: HeaderMap,
+    body: Bytes,
+    if_none_match: Option<&str>,
+) -> Response<CacheBody<Bd>> {
+    let not_modified = match (if_none_match, headers.get(header::ETAG)) {
+        (Some(if_none_match), Some(etag)) => etag
+            .to_str()
+            .ok()
+            .map_or(false, |etag| etag_matches(if_none_match, etag)),
+        _ => false,
+    };
+
+    if not_modified {
+        let mut response = Response::new(Either::Left(Once::new(Bytes::new())));
+        *response.status_mut() = StatusCode::NOT_MODIFIED;
+        for name in &[
+            header::CACHE_CONTROL,
+            header::CONTENT_LOCATION,
+            header::DATE,
+            header::ETAG,
+            header::EXPIRES,
+            header::VARY,
+            header::AGE,
+        ] {
+            for value in headers.get_all(name) {
+                response.headers_mut().append(name.clone(), value.clone());
+            }
+        }
+        response
+    } else {
+        let mut response = Response::new(Either::Left(Once::new(body)));
+        *response.headers_mut() = headers;
+        response
+    }
+}
+
+/// Computes a strong entity tag from the content of a response body.
+///
+/// The tag consists of the length and the 64-bit FNV-1a hash of the content.
+fn strong_etag(body: &[u8]) -> String {
+    let hash = body.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("\"{:x}-{:016x}\"", body.len(), hash)
+}
+
+/// Evaluates `If-None-Match` with the weak comparison.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    fn opaque(tag: &str) -> &str {
+        let tag = tag.trim();
+        if tag.starts_with("W/") {
+            &tag[2..]
+        } else {
+            tag
+        }
+    }
+
+    if if_none_match.trim() == "*" {
+        return true;
+    }
+    let etag = opaque(etag);
+    if_none_match.split(',').any(|tag| opaque(tag) == etag)
+}
+
+/// Returns the names of header fields listed in `Vary`, or `None` if it contains `*`.
+fn parse_vary(headers: &HeaderMap) -> Option<Vec<HeaderName>> {
+    let mut names = vec![];
+    for value in headers.get_all(header::VARY) {
+        let value = value.to_str().ok()?;
+        for name in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            if name == "*" {
+                return None;
+            }
+            names.push(HeaderName::from_bytes(name.to_lowercase().as_bytes()).ok()?);
+        }
+    }
+    Some(names)
+}
+
+#[derive(Debug, Default)]
+struct CacheControl {
+    no_store: bool,
+    no_cache: bool,
+    private: bool,
+    public: bool,
+    must_revalidate: bool,
+    s_maxage: bool,
+    max_age: Option<u64>,
+}
+
+impl CacheControl {
+    fn parse(headers: &HeaderMap) -> CacheControl {
+        let mut directives = CacheControl::default();
+        for value in headers.get_all(header::CACHE_CONTROL) {
+            let value = match value.to_str() {
+                Ok(value) => value,
+                Err(..) => continue,
+            };
+            for directive in value.split(',').map(str::trim) {
+                let mut kv = directive.splitn(2, '=');
+                let name = kv.next().unwrap_or("").to_lowercase();
+                let value = kv.next().map(|v| v.trim_matches('"'));
+                match &*name {
+                    "no-store" => directives.no_store = true,
+                    "no-cache" => directives.no_cache = true,
+                    "private" => directives.private = true,
+                    "public" => directives.public = true,
+                    "must-revalidate" => directives.must_revalidate = true,
+                    // `s-maxage` takes precedence over `max-age` in shared caches.
+                    "s-maxage" => {
+                        directives.s_maxage = true;
+                        directives.max_age = value.and_then(|v| v.parse().ok());
+                    }
+                    "max-age" if directives.max_age.is_none() => {
+                        directives.max_age = value.and_then(|v| v.parse().ok())
+                    }
+                    _ => {}
+                }
+            }
+        }
+        directives
+    }
+}
+
+// ==== Store ====
+
+#[derive(Debug)]
+struct Store {
+    capacity: usize,
+    inner: Mutex<StoreInner>,
+}
+
+#[derive(Debug, Default)]
+struct StoreInner {
+    entries: HashMap<String, Entry>,
+    vary: HashMap<String, Variants>,
+    /// The keys of the entries, ordered from the least recently used one.
+    lru: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+/// The header names listed in `Vary` and the keys of the stored variants for a base key.
+#[derive(Debug)]
+struct Variants {
+    names: Vec<HeaderName>,
+    keys: HashSet<String>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    base_key: String,
+    /// Whether the response may be used for the requests with `Authorization`.
+    shared: bool,
+    headers: HeaderMap,
+    body: Bytes,
+    stored_at: Instant,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+fn variant_key(base_key: &str, vary: &[HeaderName], headers: &HeaderMap) -> String {
+    let mut key = base_key.to_owned();
+    for name in vary {
+        key += "\n";
+        key += name.as_str();
+        key += ":";
+        for value in headers.get_all(name) {
+            key += &String::from_utf8_lossy(value.as_bytes());
+            key += ",";
+        }
+    }
+    key
+}
+
+impl StoreInner {
+    /// Removes an entry, along with `Vary` of its base key if it was the last variant.
+    fn remove(&mut self, key: &str) {
+        let entry = match self.entries.remove(key) {
+            Some(entry) => entry,
+            None => return,
+        };
+        self.lru.remove(&entry.last_used);
+        let is_last = match self.vary.get_mut(&entry.base_key) {
+            Some(variants) => {
+                variants.keys.remove(key);
+                variants.keys.is_empty()
+            }
+            None => false,
+        };
+        if is_last {
+            self.vary.remove(&entry.base_key);
+        }
+    }
+
+    /// Removes all variants of a base key.
+    fn remove_variants(&mut self, base_key: &str) {
+        if let Some(variants) = self.vary.remove(base_key) {
+            for key in variants.keys {
+                if let Some(entry) = self.entries.remove(&key) {
+                    self.lru.remove(&entry.last_used);
+                }
+            }
+        }
+    }
+
+    /// Marks an entry as the most recently used one.
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.lru.remove(&entry.last_used);
+            entry.last_used = tick;
+            self.lru.insert(tick, key.to_owned());
+        }
+    }
+}
+
+impl Store {
+    fn lock(&self) -> MutexGuard<'_, StoreInner> {
+        self.inner.lock().expect("the response cache is poisoned")
+    }
+
+    fn lookup(
+        &self,
+        base_key: &str,
+        headers: &HeaderMap,
+        authorized: bool,
+    ) -> Option<(HeaderMap, Bytes, u64)> {
+        let mut inner = self.lock();
+        let key = variant_key(base_key, &inner.vary.get(base_key)?.names, headers);
+
+        let now = Instant::now();
+        let (expired, shared) = {
+            let entry = inner.entries.get(&key)?;
+            (entry.expires_at <= now, entry.shared)
+        };
+        if expired {
+            inner.remove(&key);
+            return None;
+        }
+        if authorized && !shared {
+            return None;
+        }
+
+        inner.touch(&key);
+        let entry = inner.entries.get(&key)?;
+        let age = (now - entry.stored_at).as_secs();
+        Some((entry.headers.clone(), entry.body.clone(), age))
+    }
+
+    fn insert(
+        &self,
+        base_key: &str,
+        vary: Vec<HeaderName>,
+        request_headers: &HeaderMap,
+        headers: HeaderMap,
+        body: Bytes,
+        max_age: Duration,
+        shared: bool,
+    ) {
+        let mut inner = self.lock();
+        let key = variant_key(base_key, &vary, request_headers);
+
+        // The variants stored under the old `Vary` can no longer be looked up.
+        let vary_changed = inner
+            .vary
+            .get(base_key)
+            .map_or(false, |variants| variants.names != vary);
+        if vary_changed {
+            inner.remove_variants(base_key);
+        }
+
+        inner.remove(&key);
+        if inner.entries.len() >= self.capacity {
+            let lru = inner.lru.values().next().cloned();
+            if let Some(lru) = lru {
+                inner.remove(&lru);
+            }
+        }
+
+        inner
+            .vary
+            .entry(base_key.to_owned())
+            .or_insert_with(|| Variants {
+                names: vary,
+                keys: HashSet::new(),
+            })
+            .keys
+            .insert(key.clone());
+
+        inner.tick += 1;
+        let tick = inner.tick;
+        let now = Instant::now();
+        let entry = Entry {
+            base_key: base_key.to_owned(),
+            shared,
+            headers,
+            body,
+            stored_at: now,
+            expires_at: now + max_age,
+            last_used: tick,
+        };
+        inner.lru.insert(tick, key.clone());
+        inner.entries.insert(key, entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_matches() {
+        assert!(etag_matches("\"foo\"", "\"foo\""));
+        assert!(etag_matches("W/\"foo\"", "\"foo\""));
+        assert!(etag_matches("\"bar\", \"foo\"", "\"foo\""));
+        assert!(etag_matches("*", "\"foo\""));
+        assert!(!etag_matches("\"bar\"", "\"foo\""));
+    }
+
+    #[test]
+    fn test_strong_etag() {
+        assert_eq!(strong_etag(b"hello"), strong_etag(b"hello"));
+        assert_ne!(strong_etag(b"hello"), strong_etag(b"world"));
+    }
+
+    #[test]
+    fn test_cache_control() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("public, max-age=60, s-maxage=120"),
+        );
+        let directives = CacheControl::parse(&headers);
+        assert_eq!(directives.max_age, Some(120));
+        assert!(!directives.no_store);
+
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("private, max-age=60"),
+        );
+        let directives = CacheControl::parse(&headers);
+        assert!(directives.private);
+        assert_eq!(directives.max_age, Some(60));
+    }
+
+    #[test]
+    fn test_store_eviction() {
+        let store = Store {
+            capacity: 2,
+            inner: Mutex::new(StoreInner::default()),
+        };
+        let headers = HeaderMap::new();
+        let ttl = Duration::from_secs(60);
+        store.insert(
+            "GET /a",
+            vec![],
+            &headers,
+            HeaderMap::new(),
+            "a".into(),
+            ttl,
+            false,
+        );
+        store.insert(
+            "GET /b",
+            vec![],
+            &headers,
+            HeaderMap::new(),
+            "b".into(),
+            ttl,
+            false,
+        );
+        assert!(store.lookup("GET /a", &headers, false).is_some());
+        store.insert(
+            "GET /c",
+            vec![],
+            &headers,
+            HeaderMap::new(),
+            "c".into(),
+            ttl,
+            false,
+        );
+
+        assert!(store.lookup("GET /a", &headers, false).is_some());
+        assert!(store.lookup("GET /b", &headers, false).is_none());
+        assert!(store.lookup("GET /c", &headers, false).is_some());
+    }
+
+    #[test]
+    fn test_store_removes_vary_with_last_variant() {
+        let store = Store {
+            capacity: 1,
+            inner: Mutex::new(StoreInner::default()),
+        };
+        let headers = HeaderMap::new();
+        let ttl = Duration::from_secs(60);
+        for path in &["a", "b", "c"] {
+            store.insert(
+                &format!("GET /{}", path),
+                vec![header::ACCEPT_ENCODING],
+                &headers,
+                HeaderMap::new(),
+                Bytes::from(*path),
+                ttl,
+                false,
+            );
+        }
+
+        let inner = store.lock();
+        assert_eq!(inner.entries.len(), 1);
+        assert_eq!(inner.vary.len(), 1);
+        assert_eq!(inner.vary.get("GET /c").map(|v| v.keys.len()), Some(1));
+        assert_eq!(inner.lru.len(), 1);
+    }
+
+    #[test]
+    fn test_store_vary_changed() {
+        let store = Store {
+            capacity: 4,
+            inner: Mutex::new(StoreInner::default()),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT_ENCODING, HeaderValue::from_static("gzip"));
+        let ttl = Duration::from_secs(60);
+        store.insert(
+            "GET /a",
+            vec![header::ACCEPT_ENCODING],
+            &headers,
+            HeaderMap::new(),
+            "gzip".into(),
+            ttl,
+            false,
+        );
+        store.insert(
+            "GET /a",
+            vec![header::ACCEPT_LANGUAGE],
+            &headers,
+            HeaderMap::new(),
+            "any".into(),
+            ttl,
+            false,
+        );
+
+        let inner = store.lock();
+        assert_eq!(inner.entries.len(), 1);
+        assert_eq!(inner.lru.len(), 1);
+        assert_eq!(inner.vary.get("GET /a").map(|v| v.keys.len()), Some(1));
+        assert_eq!(
+            inner.vary.get("GET /a").map(|v| v.names.clone()),
+            Some(vec![header::ACCEPT_LANGUAGE])
+        );
+    }
+
+    #[test]
+    fn test_store_authorized() {
+        let store = Store {
+            capacity: 2,
+            inner: Mutex::new(StoreInner::default()),
+        };
+        let headers = HeaderMap::new();
+        let ttl = Duration::from_secs(60);
+        store.insert(
+            "GET /a",
+            vec![],
+            &headers,
+            HeaderMap::new(),
+            "a".into(),
+            ttl,
+            false,
+        );
+        store.insert(
+            "GET /b",
+            vec![],
+            &headers,
+            HeaderMap::new(),
+            "b".into(),
+            ttl,
+            true,
+        );
+
+        assert!(store.lookup("GET /a", &headers, true).is_none());
+        assert!(store.lookup("GET /a", &headers, false).is_some());
+        assert!(store.lookup("GET /b", &headers, true).is_some());
+    }
+}
@@ -0,0 +1,385 @@
+//! Components for limiting the number of requests processed concurrently.
+//!
+//! When the limit is reached, the excess requests wait in a bounded queue
+//! until a running request completes. The requests which cannot enter the queue,
+//! or wait longer than the configured time, are rejected with `503 Service Unavailable`.
+//!
+//! # Example
+//!
+//! ```
+//! # use finchers::prelude::*;
+//! # use finchers::path;
+//! use finchers::endpoints::concurrency_limit::concurrency_limit;
+//! use std::time::Duration;
+//!
+//! let limit = concurrency_limit(4).queue(16, Duration::from_secs(2));
+//!
+//! let report = path!(@get / "report")
+//!     .map(|| "report")
+//!     .wrap(limit.clone());
+//!
+//! // The handle shares the state with the wrapper.
+//! assert_eq!(limit.queue_depth(), 0);
+//! # drop(report);
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::PinMut;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use futures::Future as Future01;
+use futures_core::future::{Future, TryFuture};
+use futures_core::task;
+use futures_core::task::{Poll, Waker};
+use futures_util::ready;
+use http::header::{HeaderMap, HeaderValue};
+use http::{header, StatusCode};
+use pin_utils::{unsafe_pinned, unsafe_unpinned};
+use tokio::timer::Delay;
+
+use crate::common::poll_01_with_cx;
+use crate::endpoint::wrapper::Wrapper;
+use crate::endpoint::{Context, Endpoint, EndpointError, EndpointResult};
+use crate::error::{fail, Error, HttpError};
+
+/// Create a wrapper for creating an endpoint which limits the number of
+/// futures running concurrently to `max`.
+///
+/// By default, the excess requests are rejected immediately.
+///
+/// The clones of the returned value share the same limit, which can be used
+/// for wrapping multiple endpoints or for observing the state of the limit.
+///
+/// # Panics
+///
+/// This function panics if `max` is zero.
+pub fn concurrency_limit(max: usize) -> ConcurrencyLimit {
+    assert!(max > 0, "the limit must be greater than zero");
+    ConcurrencyLimit {
+        shared: Arc::new(Shared {
+            max,
+            state: Mutex::new(State::default()),
+        }),
+        queue_len: 0,
+        max_wait: Duration::from_secs(0),
+        retry_after: 1,
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimit {
+    shared: Arc<Shared>,
+    queue_len: usize,
+    max_wait: Duration,
+    retry_after: u64,
+}
+
+impl ConcurrencyLimit {
+    /// Enables the queue which holds up to `len` excess requests for at most `max_wait`.
+    pub fn queue(self, len: usize, max_wait: Duration) -> ConcurrencyLimit {
+        ConcurrencyLimit {
+            queue_len: len,
+            max_wait,
+            ..self
+        }
+    }
+
+    /// Sets the value of `Retry-After` header sent with the rejected requests.
+    ///
+    /// The value is rounded up to whole seconds. The default value is one second.
+    pub fn retry_after(self, retry_after: Duration) -> ConcurrencyLimit {
+        let secs = retry_after.as_secs() + if retry_after.subsec_nanos() > 0 { 1 } else { 0 };
+        ConcurrencyLimit {
+            retry_after: secs,
+            ..self
+        }
+    }
+
+    /// Returns the number of requests waiting in the queue.
+    pub fn queue_depth(&self) -> usize {
+        self.shared.lock().queue.len()
+    }
+
+    /// Returns the number of requests currently being processed.
+    pub fn in_flight(&self) -> usize {
+        self.shared.lock().in_flight
+    }
+
+    fn acquire(&self) -> Result<Acquire, Overloaded> {
+        let mut state = self.shared.lock();
+        if state.in_flight < self.shared.max && state.queue.is_empty() {
+            state.in_flight += 1;
+            return Ok(Acquire::Permitted(Permit {
+                shared: self.shared.clone(),
+            }));
+        }
+        if state.queue.len() < self.queue_len {
+            let id = state.next_id;
+            state.next_id = state.next_id.wrapping_add(1);
+            state.queue.push_back((id, None));
+            return Ok(Acquire::Queued(Waiter {
+                shared: self.shared.clone(),
+                id,
+                delay: Delay::new(Instant::now() + self.max_wait),
+            }));
+        }
+        Err(self.overloaded())
+    }
+
+    fn overloaded(&self) -> Overloaded {
+        Overloaded {
+            retry_after: self.retry_after,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    max: usize,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    in_flight: usize,
+    queue: VecDeque<(u64, Option<Waker>)>,
+    next_id: u64,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("the concurrency limit is poisoned")
+    }
+}
+
+impl State {
+    fn wake_front(&mut self) {
+        if let Some(&(_, Some(ref waker))) = self.queue.front() {
+            waker.wake();
+        }
+    }
+}
+
+enum Acquire {
+    Permitted(Permit),
+    Queued(Waiter),
+}
+
+/// A permission to run the inner future.
+#[derive(Debug)]
+struct Permit {
+    shared: Arc<Shared>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.in_flight -= 1;
+        state.wake_front();
+    }
+}
+
+/// An entry of the queue.
+#[derive(Debug)]
+struct Waiter {
+    shared: Arc<Shared>,
+    id: u64,
+    delay: Delay,
+}
+
+impl Waiter {
+    fn poll_acquire(&mut self, cx: &mut task::Context<'_>) -> Poll<Result<Permit, Option<Error>>> {
+        {
+            let mut state = self.shared.lock();
+            let is_front = state.queue.front().map_or(false, |&(id, _)| id == self.id);
+            if is_front && state.in_flight < self.shared.max {
+                state.queue.pop_front();
+                state.in_flight += 1;
+                // The next waiter may also be able to run.
+                state.wake_front();
+                return Poll::Ready(Ok(Permit {
+                    shared: self.shared.clone(),
+                }));
+            }
+            if let Some(entry) = state.queue.iter_mut().find(|entry| entry.0 == self.id) {
+                entry.1 = Some(cx.waker().clone());
+            }
+        }
+
+        let delay = &mut self.delay;
+        match poll_01_with_cx(cx, || delay.poll()) {
+            Poll::Ready(Ok(())) => Poll::Ready(Err(None)),
+            Poll::Ready(Err(err)) => Poll::Ready(Err(Some(fail(err)))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        let id = self.id;
+        state.queue.retain(|&(i, _)| i != id);
+        state.wake_front();
+    }
+}
+
+impl<'a, E> Wrapper<'a, E> for ConcurrencyLimit
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Endpoint = ConcurrencyLimitEndpoint<E>;
+
+    fn wrap(self, endpoint: E) -> Self::Endpoint {
+        ConcurrencyLimitEndpoint {
+            endpoint,
+            limit: self,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct ConcurrencyLimitEndpoint<E> {
+    endpoint: E,
+    limit: ConcurrencyLimit,
+}
+
+impl<'a, E> Endpoint<'a> for ConcurrencyLimitEndpoint<E>
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Future = ConcurrencyLimitFuture<E::Future>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        // The requests which do not match to the inner endpoint are not counted.
+        let future = self.endpoint.apply(cx)?;
+        let state = match self.limit.acquire().map_err(EndpointError::custom)? {
+            Acquire::Permitted(permit) => LimitState::Running(permit),
+            Acquire::Queued(waiter) => LimitState::Waiting(waiter),
+        };
+        Ok(ConcurrencyLimitFuture {
+            future,
+            state,
+            retry_after: self.limit.retry_after,
+        })
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct ConcurrencyLimitFuture<F> {
+    future: F,
+    state: LimitState,
+    retry_after: u64,
+}
+
+#[derive(Debug)]
+enum LimitState {
+    Waiting(Waiter),
+    Running(Permit),
+    Done,
+}
+
+impl<F> ConcurrencyLimitFuture<F> {
+    unsafe_pinned!(future: F);
+    unsafe_unpinned!(state: LimitState);
+}
+
+impl<F> Future for ConcurrencyLimitFuture<F>
+where
+    F: TryFuture<Error = Error>,
+{
+    type Output = Result<F::Ok, Error>;
+
+    fn poll(mut self: PinMut<'_, Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let polled = match *self.state() {
+            LimitState::Waiting(ref mut waiter) => Some(waiter.poll_acquire(cx)),
+            LimitState::Running(..) => None,
+            LimitState::Done => panic!("the future has already polled"),
+        };
+        match polled {
+            Some(Poll::Ready(Ok(permit))) => *self.state() = LimitState::Running(permit),
+            Some(Poll::Ready(Err(err))) => {
+                *self.state() = LimitState::Done;
+                let retry_after = self.retry_after;
+                let err = err.unwrap_or_else(|| Overloaded { retry_after }.into());
+                return Poll::Ready(Err(err));
+            }
+            Some(Poll::Pending) => return Poll::Pending,
+            None => {}
+        }
+
+        let result = ready!(self.future().try_poll(cx));
+        // Release the permit as soon as the inner future completes.
+        *self.state() = LimitState::Done;
+        Poll::Ready(result)
+    }
+}
+
+/// An error which represents that the server is too busy to process the request.
+#[derive(Debug)]
+pub struct Overloaded {
+    retry_after: u64,
+}
+
+impl Overloaded {
+    /// Returns the number of seconds after which the client can retry the request.
+    pub fn retry_after(&self) -> u64 {
+        self.retry_after
+    }
+}
+
+impl fmt::Display for Overloaded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the server is too busy to process the request")
+    }
+}
+
+impl HttpError for Overloaded {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+
+    fn headers(&self, headers: &mut HeaderMap) {
+        headers.insert(header::RETRY_AFTER, HeaderValue::from(self.retry_after));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acquire() {
+        let limit = concurrency_limit(1).queue(1, Duration::from_secs(1));
+
+        let first = limit.acquire().ok().unwrap();
+        assert!(match first {
+            Acquire::Permitted(..) => true,
+            _ => false,
+        });
+        assert_eq!(limit.in_flight(), 1);
+
+        let second = limit.acquire().ok().unwrap();
+        assert!(match second {
+            Acquire::Queued(..) => true,
+            _ => false,
+        });
+        assert_eq!(limit.queue_depth(), 1);
+
+        assert!(limit.acquire().is_err());
+
+        drop(second);
+        assert_eq!(limit.queue_depth(), 0);
+        drop(first);
+        assert_eq!(limit.in_flight(), 0);
+    }
+}
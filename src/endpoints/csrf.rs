@@ -0,0 +1,415 @@
+//! Components for protecting endpoints from Cross-Site Request Forgery.
+//!
+//! The protection is based on the "double submit" strategy: the wrapper issues
+//! a random token stored in a signed Cookie, and the client must send the same
+//! token back with every unsafe request, either in a header field or in an
+//! urlencoded form field.
+//!
+//! # Example
+//!
+//! ```
+//! # use finchers::prelude::*;
+//! # use finchers::path;
+//! use finchers::endpoints::csrf;
+//! use finchers::endpoints::csrf::CsrfToken;
+//! use finchers::input::cookie::Key;
+//!
+//! let form = path!(@get / "form" /)
+//!     .and(csrf::token())
+//!     .map(|token: CsrfToken| format!("<input name=\"csrf_token\" value=\"{}\">", token));
+//!
+//! let submit = path!(@post / "form" /)
+//!     .and(endpoints::body::text())
+//!     .map(|body: String| body);
+//!
+//! let endpoint = form.or(submit)
+//!     .wrap(csrf::csrf(Key::generate()).exempt("/webhooks"));
+//! # drop(endpoint);
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::mem;
+use std::pin::PinMut;
+
+use futures_core::future::{Future, TryFuture};
+use futures_core::task;
+use futures_core::task::Poll;
+use futures_util::try_ready;
+use pin_utils::{unsafe_pinned, unsafe_unpinned};
+
+use bytes::BytesMut;
+use http::header::HeaderName;
+use http::StatusCode;
+use hyper::body::Body;
+use serde::ser::{Serialize, Serializer};
+
+use crate::common::{constant_time_eq, random_token};
+use crate::endpoint::wrapper::Wrapper;
+use crate::endpoint::{Context, Endpoint, EndpointError, EndpointResult};
+use crate::error::{err_msg, Error};
+use crate::input::body::Payload;
+use crate::input::cookie::{Cookie, Key, SameSite};
+use crate::input::query::QueryItems;
+use crate::input::{with_get_cx, Input};
+
+/// Create a wrapper for creating an endpoint which validates CSRF tokens
+/// with the specified key.
+///
+/// The wrapped endpoint issues a token (if the client does not have it yet)
+/// and checks the submitted token on requests with an unsafe HTTP method.
+/// A request without the valid token is rejected with `403 Forbidden`.
+pub fn csrf(key: Key) -> Csrf {
+    Csrf {
+        key,
+        cookie_name: "csrf-token".into(),
+        header_name: HeaderName::from_static("x-csrf-token"),
+        field_name: "csrf_token".into(),
+        exempt: vec![],
+    }
+}
+
+#[allow(missing_docs)]
+pub struct Csrf {
+    key: Key,
+    cookie_name: Cow<'static, str>,
+    header_name: HeaderName,
+    field_name: Cow<'static, str>,
+    exempt: Vec<String>,
+}
+
+impl fmt::Debug for Csrf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Csrf")
+            .field("cookie_name", &self.cookie_name)
+            .field("header_name", &self.header_name)
+            .field("field_name", &self.field_name)
+            .field("exempt", &self.exempt)
+            .finish()
+    }
+}
+
+impl Csrf {
+    /// Sets the name of Cookie which stores the token.
+    ///
+    /// The default value is `csrf-token`.
+    pub fn cookie_name(self, name: impl Into<Cow<'static, str>>) -> Csrf {
+        Csrf {
+            cookie_name: name.into(),
+            ..self
+        }
+    }
+
+    /// Sets the name of header field which the client sends the token with.
+    ///
+    /// The default value is `x-csrf-token`.
+    pub fn header_name(self, name: HeaderName) -> Csrf {
+        Csrf {
+            header_name: name,
+            ..self
+        }
+    }
+
+    /// Sets the name of form field which the client sends the token with.
+    ///
+    /// The default value is `csrf_token`.
+    pub fn field_name(self, name: impl Into<Cow<'static, str>>) -> Csrf {
+        Csrf {
+            field_name: name.into(),
+            ..self
+        }
+    }
+
+    /// Adds a path prefix whose requests are not checked.
+    pub fn exempt(mut self, prefix: impl Into<String>) -> Csrf {
+        self.exempt.push(prefix.into());
+        self
+    }
+
+    fn is_exempt(&self, path: &str) -> bool {
+        self.exempt.iter().any(|prefix| {
+            path.starts_with(&**prefix)
+                && (path.len() == prefix.len()
+                    || prefix.ends_with('/')
+                    || path[prefix.len()..].starts_with('/'))
+        })
+    }
+
+    fn issue_token(&self, input: PinMut<'_, Input>) -> Result<CsrfToken, Error> {
+        let mut cookies = input.cookies()?;
+        let mut jar = cookies.signed(&self.key);
+        let token = match jar.get(&self.cookie_name) {
+            Some(cookie) => cookie.value().to_owned(),
+            None => {
+                let token = random_token(32);
+                jar.add(
+                    Cookie::build(self.cookie_name.clone(), token.clone())
+                        .path("/")
+                        .http_only(true)
+                        .same_site(SameSite::Strict)
+                        .finish(),
+                );
+                token
+            }
+        };
+        Ok(CsrfToken(token))
+    }
+
+    fn verify(&self, mut input: PinMut<'_, Input>, token: &CsrfToken) -> Result<State, Error> {
+        if input.method().is_safe() || self.is_exempt(input.uri().path()) {
+            return Ok(State::Verified);
+        }
+
+        if let Some(submitted) = input.headers().get(&self.header_name) {
+            return if constant_time_eq(submitted.as_bytes(), token.0.as_bytes()) {
+                Ok(State::Verified)
+            } else {
+                Err(mismatched_token())
+            };
+        }
+
+        match input.reborrow().content_type()? {
+            Some(m)
+                if m.type_() == mime::APPLICATION && m.subtype() == mime::WWW_FORM_URLENCODED =>
+            {
+                Ok(State::Form(token.0.clone()))
+            }
+            _ => Err(missing_token()),
+        }
+    }
+}
+
+impl<'a, E> Wrapper<'a, E> for Csrf
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Endpoint = CsrfEndpoint<E>;
+
+    fn wrap(self, endpoint: E) -> Self::Endpoint {
+        CsrfEndpoint {
+            endpoint,
+            csrf: self,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct CsrfEndpoint<E> {
+    endpoint: E,
+    csrf: Csrf,
+}
+
+impl<'a, E> Endpoint<'a> for CsrfEndpoint<E>
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Future = CsrfFuture<'a, E::Future>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let future = self.endpoint.apply(cx)?;
+
+        let token = self
+            .csrf
+            .issue_token(cx.input())
+            .map_err(EndpointError::custom)?;
+        let state = self
+            .csrf
+            .verify(cx.input(), &token)
+            .map_err(EndpointError::custom)?;
+        cx.input().extensions_mut().insert(token);
+
+        Ok(CsrfFuture {
+            future,
+            state,
+            field_name: &self.csrf.field_name,
+        })
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct CsrfFuture<'a, Fut> {
+    future: Fut,
+    state: State,
+    field_name: &'a str,
+}
+
+#[derive(Debug)]
+enum State {
+    Verified,
+    Form(String),
+    Receiving(Payload, BytesMut, String),
+}
+
+impl<'a, Fut> CsrfFuture<'a, Fut> {
+    unsafe_pinned!(future: Fut);
+    unsafe_unpinned!(state: State);
+}
+
+impl<'a, Fut> Future for CsrfFuture<'a, Fut>
+where
+    Fut: TryFuture<Error = Error>,
+{
+    type Output = Result<Fut::Ok, Error>;
+
+    fn poll(mut self: PinMut<'_, Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        loop {
+            let verified = match self.state() {
+                State::Verified => true,
+                State::Form(..) => false,
+                State::Receiving(ref mut payload, ref mut buf, _) => {
+                    let mut payload = unsafe { PinMut::new_unchecked(payload) };
+                    while let Some(chunk) = try_ready!(payload.reborrow().poll_data(cx)) {
+                        buf.extend_from_slice(&*chunk);
+                    }
+                    false
+                }
+            };
+            if verified {
+                return self.future().try_poll(cx);
+            }
+
+            match mem::replace(self.state(), State::Verified) {
+                State::Form(token) => {
+                    let payload = match with_get_cx(|input| input.payload()) {
+                        Some(payload) => payload,
+                        None => return Poll::Ready(Err(missing_token())),
+                    };
+                    *self.state() = State::Receiving(payload, BytesMut::new(), token);
+                }
+                State::Receiving(_, buf, token) => {
+                    let body = buf.freeze();
+                    match find_field(&body, self.field_name) {
+                        Some(ref submitted)
+                            if constant_time_eq(submitted.as_bytes(), token.as_bytes()) => {}
+                        Some(..) => return Poll::Ready(Err(mismatched_token())),
+                        None => return Poll::Ready(Err(missing_token())),
+                    }
+                    // Restore the received body so that the subsequent endpoints can read it.
+                    with_get_cx(|input| input.replace_body(Body::from(body)));
+                }
+                State::Verified => unreachable!(),
+            }
+        }
+    }
+}
+
+fn find_field(body: &[u8], name: &str) -> Option<String> {
+    let s = std::str::from_utf8(body).ok()?;
+    let mut items = unsafe { QueryItems::new_unchecked(s) };
+    items
+        .find(|(key, _)| key.url_decode().ok().map_or(false, |key| key == name))
+        .and_then(|(_, value)| value.url_decode().ok().map(Cow::into_owned))
+}
+
+fn missing_token() -> Error {
+    err_msg(StatusCode::FORBIDDEN, "missing CSRF token")
+}
+
+fn mismatched_token() -> Error {
+    err_msg(StatusCode::FORBIDDEN, "invalid CSRF token")
+}
+
+// ==== Token ====
+
+/// A CSRF token issued for the current request.
+///
+/// The value should be embedded into the forms (or passed to the client-side
+/// scripts) so that the client can send it back with unsafe requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    /// Returns the string representation of this token.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for CsrfToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for CsrfToken {
+    fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        ser.serialize_str(&self.0)
+    }
+}
+
+/// Create an endpoint which returns the CSRF token issued for the current request.
+///
+/// This endpoint must be used inside of an endpoint wrapped by `csrf()`.
+/// Otherwise, the returned future will fail with `500 Internal Server Error`.
+#[inline]
+pub fn token() -> Token {
+    (Token { _priv: () }).with_output::<(CsrfToken,)>()
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone)]
+pub struct Token {
+    _priv: (),
+}
+
+impl<'a> Endpoint<'a> for Token {
+    type Output = (CsrfToken,);
+    type Future = TokenFuture;
+
+    fn apply(&'a self, _: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        Ok(TokenFuture { _priv: () })
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct TokenFuture {
+    _priv: (),
+}
+
+impl Future for TokenFuture {
+    type Output = Result<(CsrfToken,), Error>;
+
+    fn poll(self: PinMut<'_, Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        Poll::Ready(
+            with_get_cx(|input| input.extensions().get::<CsrfToken>().cloned())
+                .map(|token| (token,))
+                .ok_or_else(|| {
+                    err_msg(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "The CSRF token is not issued. Wrap the endpoint with `csrf()`.",
+                    )
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_exempt() {
+        let csrf = csrf(Key::generate()).exempt("/webhooks").exempt("/api/");
+        assert!(csrf.is_exempt("/webhooks"));
+        assert!(csrf.is_exempt("/webhooks/github"));
+        assert!(!csrf.is_exempt("/webhooksfoo"));
+        assert!(csrf.is_exempt("/api/posts"));
+        assert!(!csrf.is_exempt("/"));
+    }
+
+    #[test]
+    fn test_find_field() {
+        let body = b"title=Hello+world&csrf_token=abc%2Fdef";
+        assert_eq!(find_field(body, "csrf_token"), Some("abc/def".into()));
+        assert_eq!(find_field(body, "title"), Some("Hello world".into()));
+        assert_eq!(find_field(body, "missing"), None);
+    }
+}
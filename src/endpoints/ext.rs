@@ -0,0 +1,120 @@
+//! An endpoint for extracting the typed per-request extensions.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::PinMut;
+
+use futures_core::future::Future;
+use futures_core::task;
+use futures_core::task::Poll;
+use http::StatusCode;
+
+use crate::endpoint::{Context, Endpoint, EndpointResult};
+use crate::error::{err_msg, Error};
+use crate::input::with_get_cx;
+
+/// Create an endpoint which extracts a value of `T` from the extensions of the current request.
+///
+/// The value is cloned when the returned future is polled, so the values inserted
+/// by the preceding endpoints (or by wrappers) can be read.
+/// If the value is missing, the endpoint fails with `500 Internal Server Error`.
+///
+/// # Example
+///
+/// ```
+/// # use finchers::prelude::*;
+/// # use finchers::path;
+/// use finchers::endpoint::wrapper::before_apply;
+///
+/// #[derive(Debug, Clone)]
+/// struct Tenant(String);
+///
+/// let endpoint = path!(@get / "tenant")
+///     .and(endpoints::ext::<Tenant>())
+///     .map(|tenant: Tenant| tenant.0)
+///     .wrap(before_apply(|cx| {
+///         let tenant = cx
+///             .input()
+///             .headers()
+///             .get("x-tenant")
+///             .and_then(|h| h.to_str().ok())
+///             .unwrap_or("default")
+///             .to_owned();
+///         cx.input().extensions_mut().insert(Tenant(tenant));
+///         Ok(())
+///     }));
+/// # drop(endpoint);
+/// ```
+#[inline]
+pub fn ext<T>() -> Ext<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    (Ext {
+        _marker: PhantomData,
+    }).with_output::<(T,)>()
+}
+
+#[allow(missing_docs)]
+pub struct Ext<T> {
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> fmt::Debug for Ext<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ext").finish()
+    }
+}
+
+impl<T> Copy for Ext<T> {}
+
+impl<T> Clone for Ext<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Endpoint<'a> for Ext<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type Output = (T,);
+    type Future = ExtFuture<T>;
+
+    fn apply(&'a self, _: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        Ok(ExtFuture {
+            _marker: PhantomData,
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct ExtFuture<T> {
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> fmt::Debug for ExtFuture<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtFuture").finish()
+    }
+}
+
+impl<T> Future for ExtFuture<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type Output = Result<(T,), Error>;
+
+    fn poll(self: PinMut<'_, Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        Poll::Ready(
+            with_get_cx(|input| input.extensions().get::<T>().cloned())
+                .map(|value| (value,))
+                .ok_or_else(|| {
+                    err_msg(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "the requested extension is not set",
+                    )
+                }),
+        )
+    }
+}
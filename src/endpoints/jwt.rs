@@ -0,0 +1,569 @@
+//! An endpoint for verifying JSON Web Tokens sent with the Bearer authentication scheme.
+//!
+//! # Example
+//!
+//! ```
+//! # use finchers::prelude::*;
+//! # use finchers::path;
+//! # use serde::Deserialize;
+//! use finchers::endpoints::jwt;
+//! use finchers::endpoints::jwt::KeySet;
+//! use std::time::Duration;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Claims {
+//!     sub: String,
+//! }
+//!
+//! let keys = KeySet::new().hs256("my-secret");
+//!
+//! let endpoint = path!(@get / "me")
+//!     .and(jwt::jwt::<Claims>(keys)
+//!         .audience("my-service")
+//!         .leeway(Duration::from_secs(30)))
+//!     .map(|claims: Claims| format!("Hello, {}", claims.sub));
+//! # drop(endpoint);
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use base64;
+use failure::Fail;
+use futures_util::future::{ready, Ready};
+use http::header;
+use hyperx::header::{Authorization, Bearer, Header};
+use ring::{digest, hmac, signature};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json;
+use serde_json::Value;
+use time;
+use untrusted;
+
+use crate::endpoint::{Context, Endpoint, EndpointError, EndpointResult};
+use crate::endpoints::auth::{Challenge, Unauthorized};
+use crate::error::Error;
+
+// ==== KeySet ====
+
+/// The signature algorithms supported by `KeySet`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Algorithm {
+    /// HMAC using SHA-256.
+    HS256,
+    /// RSASSA-PKCS1-v1_5 using SHA-256.
+    RS256,
+    /// ECDSA using P-256 and SHA-256.
+    ES256,
+}
+
+impl Algorithm {
+    fn from_name(name: &str) -> Option<Algorithm> {
+        match name {
+            "HS256" => Some(Algorithm::HS256),
+            "RS256" => Some(Algorithm::RS256),
+            "ES256" => Some(Algorithm::ES256),
+            _ => None,
+        }
+    }
+}
+
+enum KeyMaterial {
+    Hmac(Vec<u8>),
+    RsaDer(Vec<u8>),
+    RsaComponents { n: Vec<u8>, e: Vec<u8> },
+    Ec(Vec<u8>),
+}
+
+impl fmt::Debug for KeyMaterial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The key materials are not shown in order to avoid leaking the secrets.
+        f.write_str(match self {
+            KeyMaterial::Hmac(..) => "Hmac",
+            KeyMaterial::RsaDer(..) => "RsaDer",
+            KeyMaterial::RsaComponents { .. } => "RsaComponents",
+            KeyMaterial::Ec(..) => "Ec",
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Key {
+    kid: Option<String>,
+    material: KeyMaterial,
+}
+
+impl Key {
+    fn algorithm(&self) -> Algorithm {
+        match self.material {
+            KeyMaterial::Hmac(..) => Algorithm::HS256,
+            KeyMaterial::RsaDer(..) | KeyMaterial::RsaComponents { .. } => Algorithm::RS256,
+            KeyMaterial::Ec(..) => Algorithm::ES256,
+        }
+    }
+
+    fn verify(&self, message: &[u8], sig: &[u8]) -> bool {
+        if let KeyMaterial::Hmac(ref secret) = self.material {
+            let key = hmac::VerificationKey::new(&digest::SHA256, secret);
+            return hmac::verify(&key, message, sig).is_ok();
+        }
+
+        let message = untrusted::Input::from(message);
+        let sig = untrusted::Input::from(sig);
+        match self.material {
+            KeyMaterial::Hmac(..) => unreachable!(),
+            KeyMaterial::RsaDer(ref der) => signature::verify(
+                &signature::RSA_PKCS1_2048_8192_SHA256,
+                untrusted::Input::from(der),
+                message,
+                sig,
+            )
+            .is_ok(),
+            KeyMaterial::RsaComponents { ref n, ref e } => signature::primitive::verify_rsa(
+                &signature::RSA_PKCS1_2048_8192_SHA256,
+                (untrusted::Input::from(n), untrusted::Input::from(e)),
+                message,
+                sig,
+            )
+            .is_ok(),
+            KeyMaterial::Ec(ref point) => signature::verify(
+                &signature::ECDSA_P256_SHA256_FIXED,
+                untrusted::Input::from(point),
+                message,
+                sig,
+            )
+            .is_ok(),
+        }
+    }
+}
+
+/// A set of keys used for verifying the signature of tokens.
+#[derive(Debug, Default)]
+pub struct KeySet {
+    keys: Vec<Key>,
+}
+
+impl KeySet {
+    /// Create an empty `KeySet`.
+    pub fn new() -> KeySet {
+        Default::default()
+    }
+
+    fn push(mut self, kid: Option<String>, material: KeyMaterial) -> KeySet {
+        self.keys.push(Key { kid, material });
+        self
+    }
+
+    /// Adds a shared secret used for verifying the tokens signed with HS256.
+    pub fn hs256(self, secret: impl Into<Vec<u8>>) -> KeySet {
+        self.push(None, KeyMaterial::Hmac(secret.into()))
+    }
+
+    /// Adds a DER-encoded RSA public key (`RSAPublicKey` in PKCS#1) used for
+    /// verifying the tokens signed with RS256.
+    pub fn rs256(self, der: impl Into<Vec<u8>>) -> KeySet {
+        self.push(None, KeyMaterial::RsaDer(der.into()))
+    }
+
+    /// Adds an RSA public key from its big-endian modulus and exponent.
+    pub fn rs256_components(self, n: impl Into<Vec<u8>>, e: impl Into<Vec<u8>>) -> KeySet {
+        self.push(
+            None,
+            KeyMaterial::RsaComponents {
+                n: n.into(),
+                e: e.into(),
+            },
+        )
+    }
+
+    /// Adds a P-256 public key, encoded as an uncompressed point, used for
+    /// verifying the tokens signed with ES256.
+    pub fn es256(self, point: impl Into<Vec<u8>>) -> KeySet {
+        self.push(None, KeyMaterial::Ec(point.into()))
+    }
+
+    /// Create a `KeySet` from a JWKS (JSON Web Key Set) document.
+    ///
+    /// The keys whose type or curve is not supported and the keys
+    /// for encryption are ignored.
+    pub fn from_jwks(document: &str) -> Result<KeySet, JwksError> {
+        let jwks: Jwks =
+            serde_json::from_str(document).map_err(|cause| JwksError::Parse { cause })?;
+        jwks.keys
+            .into_iter()
+            .try_fold(KeySet::new(), |keys, jwk| -> Result<_, JwksError> {
+                if jwk.use_.as_ref().map_or(false, |u| u != "sig") {
+                    return Ok(keys);
+                }
+                let material = match (jwk.kty.as_str(), jwk.crv.as_ref().map(|s| s.as_str())) {
+                    ("oct", _) => KeyMaterial::Hmac(jwk.param("k")?),
+                    ("RSA", _) => KeyMaterial::RsaComponents {
+                        n: jwk.param("n")?,
+                        e: jwk.param("e")?,
+                    },
+                    ("EC", Some("P-256")) => {
+                        let mut point = vec![0x04];
+                        point.extend(jwk.param("x")?);
+                        point.extend(jwk.param("y")?);
+                        KeyMaterial::Ec(point)
+                    }
+                    _ => return Ok(keys),
+                };
+                Ok(keys.push(jwk.kid, material))
+            })
+    }
+
+    /// Create a `KeySet` from a JWKS document stored at the specified path.
+    pub fn from_jwks_file(path: impl AsRef<Path>) -> Result<KeySet, JwksError> {
+        let document = fs::read_to_string(path).map_err(|cause| JwksError::Io { cause })?;
+        KeySet::from_jwks(&document)
+    }
+
+    fn verify(&self, alg: Algorithm, kid: Option<&str>, message: &[u8], sig: &[u8]) -> bool {
+        self.keys
+            .iter()
+            .filter(|key| key.algorithm() == alg)
+            .filter(|key| match (kid, key.kid.as_ref()) {
+                (Some(kid), Some(key_id)) => kid == key_id,
+                _ => true,
+            })
+            .any(|key| key.verify(message, sig))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Jwk {
+    kty: String,
+    kid: Option<String>,
+    #[serde(rename = "use")]
+    use_: Option<String>,
+    crv: Option<String>,
+    k: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+    x: Option<String>,
+    y: Option<String>,
+}
+
+impl Jwk {
+    fn param(&self, name: &'static str) -> Result<Vec<u8>, JwksError> {
+        let value = match name {
+            "k" => &self.k,
+            "n" => &self.n,
+            "e" => &self.e,
+            "x" => &self.x,
+            "y" => &self.y,
+            _ => unreachable!(),
+        };
+        let value = value
+            .as_ref()
+            .ok_or_else(|| JwksError::InvalidKey { name })?;
+        decode_segment(value).map_err(|_| JwksError::InvalidKey { name })
+    }
+}
+
+/// An error which will be returned when loading a JWKS document.
+#[derive(Debug, Fail)]
+pub enum JwksError {
+    #[allow(missing_docs)]
+    #[fail(display = "failed to read the JWKS document: {}", cause)]
+    Io { cause: io::Error },
+
+    #[allow(missing_docs)]
+    #[fail(display = "failed to parse the JWKS document: {}", cause)]
+    Parse { cause: serde_json::Error },
+
+    #[allow(missing_docs)]
+    #[fail(display = "missing or invalid key parameter: `{}`", name)]
+    InvalidKey { name: &'static str },
+}
+
+// ==== Jwt ====
+
+/// Create an endpoint which verifies the bearer token as a JWT and extracts its claims.
+///
+/// The claims are deserialized into `C` after verifying the signature and
+/// validating the registered claims (`exp`, `nbf`, `aud` and `iss`).
+/// If the token is missing or invalid, the endpoint returns an error
+/// which will be converted into a `401 Unauthorized` response.
+pub fn jwt<C>(keys: KeySet) -> Jwt<C>
+where
+    C: DeserializeOwned,
+{
+    Jwt {
+        keys: Arc::new(keys),
+        audience: None,
+        issuer: None,
+        leeway: 0,
+        realm: None,
+        _marker: PhantomData,
+    }
+}
+
+#[allow(missing_docs)]
+pub struct Jwt<C> {
+    keys: Arc<KeySet>,
+    audience: Option<String>,
+    issuer: Option<String>,
+    leeway: i64,
+    realm: Option<String>,
+    _marker: PhantomData<fn() -> C>,
+}
+
+impl<C> fmt::Debug for Jwt<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Jwt")
+            .field("keys", &self.keys)
+            .field("audience", &self.audience)
+            .field("issuer", &self.issuer)
+            .field("leeway", &self.leeway)
+            .field("realm", &self.realm)
+            .finish()
+    }
+}
+
+impl<C> Clone for Jwt<C> {
+    fn clone(&self) -> Self {
+        Jwt {
+            keys: self.keys.clone(),
+            audience: self.audience.clone(),
+            issuer: self.issuer.clone(),
+            leeway: self.leeway,
+            realm: self.realm.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<C> Jwt<C> {
+    /// Sets the value which must be contained in the `aud` claim.
+    pub fn audience(self, audience: impl Into<String>) -> Jwt<C> {
+        Jwt {
+            audience: Some(audience.into()),
+            ..self
+        }
+    }
+
+    /// Sets the value which must be equal to the `iss` claim.
+    pub fn issuer(self, issuer: impl Into<String>) -> Jwt<C> {
+        Jwt {
+            issuer: Some(issuer.into()),
+            ..self
+        }
+    }
+
+    /// Sets the amount of clock skew tolerated when validating `exp` and `nbf`.
+    ///
+    /// The default value is zero.
+    pub fn leeway(self, leeway: Duration) -> Jwt<C> {
+        Jwt {
+            leeway: leeway.as_secs() as i64,
+            ..self
+        }
+    }
+
+    /// Sets the realm sent with the `WWW-Authenticate` header.
+    pub fn realm(self, realm: impl Into<String>) -> Jwt<C> {
+        Jwt {
+            realm: Some(realm.into()),
+            ..self
+        }
+    }
+
+    fn challenge(&self) -> Challenge {
+        match self.realm {
+            Some(ref realm) => Challenge::new("Bearer", realm.clone()),
+            None => Challenge::bare("Bearer"),
+        }
+    }
+
+    fn invalid_token(&self, reason: &'static str) -> EndpointError {
+        EndpointError::custom(Unauthorized::new(
+            self.challenge().param("error", "invalid_token"),
+            reason,
+        ))
+    }
+
+    fn decode(&self, token: &str) -> Result<Value, &'static str> {
+        let mut parts = token.rsplitn(2, '.');
+        let sig = parts.next().ok_or("malformed token")?;
+        let message = parts.next().ok_or("malformed token")?;
+
+        let mut parts = message.splitn(2, '.');
+        let header = parts.next().ok_or("malformed token")?;
+        let payload = parts.next().ok_or("malformed token")?;
+
+        let header: JwtHeader = decode_json(header)?;
+        let alg = Algorithm::from_name(&header.alg).ok_or("unsupported algorithm")?;
+        let sig = decode_segment(sig).map_err(|_| "malformed token")?;
+        let kid = header.kid.as_ref().map(|s| s.as_str());
+        if !self.keys.verify(alg, kid, message.as_bytes(), &sig) {
+            return Err("invalid signature");
+        }
+
+        let claims: Value = decode_json(payload)?;
+        self.validate(&claims, time::get_time().sec)?;
+        Ok(claims)
+    }
+
+    fn validate(&self, claims: &Value, now: i64) -> Result<(), &'static str> {
+        if let Some(exp) = claims.get("exp") {
+            let exp = exp.as_i64().ok_or("invalid `exp` claim")?;
+            if now >= exp + self.leeway {
+                return Err("the token has expired");
+            }
+        }
+
+        if let Some(nbf) = claims.get("nbf") {
+            let nbf = nbf.as_i64().ok_or("invalid `nbf` claim")?;
+            if now + self.leeway < nbf {
+                return Err("the token is not valid yet");
+            }
+        }
+
+        if let Some(ref audience) = self.audience {
+            let matched = match claims.get("aud") {
+                Some(Value::String(aud)) => aud == audience,
+                Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(audience)),
+                _ => false,
+            };
+            if !matched {
+                return Err("invalid audience");
+            }
+        }
+
+        if let Some(ref issuer) = self.issuer {
+            if claims.get("iss").and_then(Value::as_str) != Some(issuer) {
+                return Err("invalid issuer");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, C> Endpoint<'a> for Jwt<C>
+where
+    C: DeserializeOwned + 'a,
+{
+    type Output = (C,);
+    type Future = Ready<Result<Self::Output, Error>>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let token = match cx.input().headers().get(header::AUTHORIZATION) {
+            Some(h) => Authorization::<Bearer>::parse_header(&h.as_bytes().into())
+                .map(|Authorization(bearer)| bearer.token)
+                .map_err(|_| self.invalid_token("malformed authorization header"))?,
+            None => {
+                return Err(EndpointError::custom(Unauthorized::new(
+                    self.challenge(),
+                    "missing bearer token",
+                )))
+            }
+        };
+
+        let claims = self
+            .decode(&token)
+            .map_err(|reason| self.invalid_token(reason))?;
+        let claims =
+            serde_json::from_value(claims).map_err(|_| self.invalid_token("invalid claims"))?;
+
+        Ok(ready(Ok((claims,))))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JwtHeader {
+    alg: String,
+    kid: Option<String>,
+}
+
+fn decode_segment(segment: &str) -> Result<Vec<u8>, base64::DecodeError> {
+    base64::decode_config(segment, base64::URL_SAFE_NO_PAD)
+}
+
+fn decode_json<T: DeserializeOwned>(segment: &str) -> Result<T, &'static str> {
+    let bytes = decode_segment(segment).map_err(|_| "malformed token")?;
+    serde_json::from_slice(&bytes).map_err(|_| "malformed token")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_jwks() {
+        let keys = KeySet::from_jwks(
+            r#"{
+                "keys": [
+                    { "kty": "oct", "kid": "hmac", "k": "c2VjcmV0" },
+                    { "kty": "RSA", "kid": "rsa", "n": "AQAB", "e": "AQAB" },
+                    { "kty": "EC", "kid": "ec", "crv": "P-256", "x": "AQAB", "y": "AQAB" },
+                    { "kty": "EC", "kid": "ec2", "crv": "P-521", "x": "AQAB", "y": "AQAB" },
+                    { "kty": "RSA", "kid": "enc", "use": "enc", "n": "AQAB", "e": "AQAB" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let kinds: Vec<_> = keys
+            .keys
+            .iter()
+            .map(|key| (key.kid.as_ref().unwrap().as_str(), key.algorithm()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("hmac", Algorithm::HS256),
+                ("rsa", Algorithm::RS256),
+                ("ec", Algorithm::ES256),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_jwks_invalid_key() {
+        match KeySet::from_jwks(r#"{ "keys": [{ "kty": "RSA", "n": "AQAB" }] }"#) {
+            Err(JwksError::InvalidKey { name: "e" }) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let endpoint = jwt::<Value>(KeySet::new())
+            .audience("my-service")
+            .issuer("issuer")
+            .leeway(Duration::from_secs(10));
+
+        let claims = json!({
+            "aud": ["other", "my-service"],
+            "iss": "issuer",
+            "exp": 1000,
+            "nbf": 500,
+        });
+        assert!(endpoint.validate(&claims, 600).is_ok());
+        assert!(endpoint.validate(&claims, 1005).is_ok());
+        assert!(endpoint.validate(&claims, 1010).is_err());
+        assert!(endpoint.validate(&claims, 495).is_ok());
+        assert!(endpoint.validate(&claims, 480).is_err());
+
+        let claims = json!({ "aud": "other", "iss": "issuer" });
+        assert_eq!(endpoint.validate(&claims, 0), Err("invalid audience"));
+
+        let claims = json!({ "aud": "my-service" });
+        assert_eq!(endpoint.validate(&claims, 0), Err("invalid issuer"));
+    }
+}
@@ -1,4 +1,11 @@
 //! Wrapper for logging.
+//!
+//! In addition to `logging_fn()`, which accepts an arbitrary function, this module provides
+//! some built-in formats of access logs:
+//!
+//! * `common()` - [Common Log Format](https://httpd.apache.org/docs/current/logs.html#common)
+//! * `combined()` - [Combined Log Format](https://httpd.apache.org/docs/current/logs.html#combined)
+//! * `json()` - a JSON object per line
 
 use futures_core::future::{Future, TryFuture};
 use futures_core::task;
@@ -8,13 +15,17 @@ use futures_util::try_ready;
 use pin_utils::unsafe_pinned;
 use std::pin::PinMut;
 
-use http::Response;
-use http::StatusCode;
+use http::header;
+use http::{Response, StatusCode, Version};
 use log::info;
-use std::time::Instant;
+use serde_json::json;
+use std::net::SocketAddr;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use time;
 
 use crate::endpoint::wrapper::Wrapper;
 use crate::endpoint::{Context, Endpoint, EndpointResult};
+use crate::endpoints::request_id::RequestId;
 use crate::error::Error;
 use crate::error::Never;
 use crate::input::{with_get_cx, Input};
@@ -24,17 +35,43 @@ use crate::output::{Output, OutputContext};
 /// Create a wrapper for creating an endpoint which dumps log
 /// after resolving the future.
 pub fn logging() -> Logging<impl Fn(Info<'_>) + Copy + Clone> {
-    logging_fn(|info: Info<'_>| {
-        info!(
+    logging_fn(|info: Info<'_>| match info.request_id() {
+        Some(request_id) => info!(
+            "{} {} -> {} ({:?}) [{}]",
+            info.input.method(),
+            info.input.uri(),
+            info.status,
+            info.start.elapsed(),
+            request_id
+        ),
+        None => info!(
             "{} {} -> {} ({:?})",
             info.input.method(),
             info.input.uri(),
             info.status,
             info.start.elapsed()
-        );
+        ),
     })
 }
 
+/// Create a wrapper for creating an endpoint which dumps an access log
+/// in the Common Log Format.
+pub fn common() -> Logging<impl Fn(Info<'_>) + Copy + Clone> {
+    logging_fn(|info: Info<'_>| info!("{}", format_common(&info)))
+}
+
+/// Create a wrapper for creating an endpoint which dumps an access log
+/// in the Combined Log Format.
+pub fn combined() -> Logging<impl Fn(Info<'_>) + Copy + Clone> {
+    logging_fn(|info: Info<'_>| info!("{}", format_combined(&info)))
+}
+
+/// Create a wrapper for creating an endpoint which dumps an access log
+/// as a JSON object.
+pub fn json() -> Logging<impl Fn(Info<'_>) + Copy + Clone> {
+    logging_fn(|info: Info<'_>| info!("{}", format_json(&info)))
+}
+
 /// Create a wrapper for creating an endpoint which dumps a log
 /// using the specified function.
 pub fn logging_fn<F>(f: F) -> Logging<F>
@@ -85,11 +122,13 @@ where
 
     fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
         let start = Instant::now();
+        let received_at = SystemTime::now();
         let future = self.endpoint.apply(cx)?;
         Ok(WithLoggingFuture {
             future,
             f: &self.f,
             start,
+            received_at,
         })
     }
 }
@@ -100,6 +139,7 @@ pub struct WithLoggingFuture<'a, Fut, F: 'a> {
     future: Fut,
     f: &'a F,
     start: Instant,
+    received_at: SystemTime,
 }
 
 impl<'a, Fut, F> WithLoggingFuture<'a, Fut, F> {
@@ -128,7 +168,9 @@ where
         with_get_cx(|mut input| {
             (self.f)(Info {
                 status: response.status(),
+                body_size: response.body().content_length(),
                 start: self.start,
+                received_at: self.received_at,
                 input: input.reborrow(),
                 _priv: (),
             });
@@ -156,7 +198,176 @@ impl<Bd: Payload> Output for LoggedResponse<Bd> {
 #[derive(Debug)]
 pub struct Info<'a> {
     pub status: StatusCode,
+    pub body_size: Option<u64>,
     pub start: Instant,
+    pub received_at: SystemTime,
     pub input: PinMut<'a, Input>,
     _priv: (),
 }
+
+impl<'a> Info<'a> {
+    /// Returns the identifier associated with the request, if available.
+    ///
+    /// The identifier is set by the wrapper `endpoints::request_id::request_id()`.
+    pub fn request_id(&self) -> Option<&RequestId> {
+        self.input.extensions().get::<RequestId>()
+    }
+
+    /// Returns the address of the client, if available.
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.input.remote_addr()
+    }
+
+    /// Returns the value of `User-Agent` header, if available.
+    pub fn user_agent(&self) -> Option<&str> {
+        self.header_str(header::USER_AGENT)
+    }
+
+    /// Returns the value of `Referer` header, if available.
+    pub fn referer(&self) -> Option<&str> {
+        self.header_str(header::REFERER)
+    }
+
+    /// Returns the HTTP version of the request.
+    pub fn version(&self) -> Version {
+        self.input.version()
+    }
+
+    fn header_str(&self, name: header::HeaderName) -> Option<&str> {
+        self.input
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    }
+}
+
+fn format_common(info: &Info<'_>) -> String {
+    let timestamp = time::at_utc(time::Timespec::new(unix_time(info.received_at) as i64, 0));
+    format!(
+        "{} - - [{}] \"{} {} {:?}\" {} {}",
+        info.remote_addr()
+            .map_or_else(|| "-".to_owned(), |addr| addr.ip().to_string()),
+        timestamp.strftime("%d/%b/%Y:%H:%M:%S +0000").unwrap(),
+        info.input.method(),
+        info.input.uri(),
+        info.version(),
+        info.status.as_u16(),
+        info.body_size
+            .map_or_else(|| "-".to_owned(), |size| size.to_string()),
+    )
+}
+
+fn format_combined(info: &Info<'_>) -> String {
+    format!(
+        "{} \"{}\" \"{}\"",
+        format_common(info),
+        escape_quoted(info.referer().unwrap_or("-")),
+        escape_quoted(info.user_agent().unwrap_or("-")),
+    )
+}
+
+fn format_json(info: &Info<'_>) -> String {
+    let elapsed = info.start.elapsed();
+    json!({
+        "timestamp": unix_time(info.received_at),
+        "method": info.input.method().as_str(),
+        "uri": info.input.uri().to_string(),
+        "version": format!("{:?}", info.version()),
+        "status": info.status.as_u16(),
+        "body_size": info.body_size,
+        "elapsed_ms": elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis()),
+        "remote_addr": info.remote_addr().map(|addr| addr.ip().to_string()),
+        "user_agent": info.user_agent(),
+        "referer": info.referer(),
+        "request_id": info.request_id(),
+    })
+    .to_string()
+}
+
+fn unix_time(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn escape_quoted(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::body::ReqBody;
+    use crate::input::ConnectionInfo;
+    use http::Request;
+    use pin_utils::pin_mut;
+    use serde_json::Value;
+    use std::time::Duration;
+
+    fn with_info(remote_addr: Option<SocketAddr>, f: impl FnOnce(Info<'_>)) {
+        let mut request = Request::get("/foo?bar=baz")
+            .header("user-agent", "curl/7.61.0")
+            .header("referer", "http://example.com/\"index\"")
+            .body(ReqBody::from_hyp(Default::default()))
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectionInfo::new(remote_addr, None, false));
+        let input = Input::new(request);
+        pin_mut!(input);
+        f(Info {
+            status: StatusCode::OK,
+            body_size: Some(42),
+            start: Instant::now(),
+            received_at: UNIX_EPOCH + Duration::from_secs(1_000_000_000),
+            input,
+            _priv: (),
+        })
+    }
+
+    #[test]
+    fn test_format_common() {
+        with_info(None, |info| {
+            assert_eq!(
+                format_common(&info),
+                "- - - [09/Sep/2001:01:46:40 +0000] \"GET /foo?bar=baz HTTP/1.1\" 200 42"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_combined() {
+        with_info(None, |info| {
+            assert_eq!(
+                format_combined(&info),
+                "- - - [09/Sep/2001:01:46:40 +0000] \"GET /foo?bar=baz HTTP/1.1\" 200 42 \
+                 \"http://example.com/\\\"index\\\"\" \"curl/7.61.0\""
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_json() {
+        with_info(None, |info| {
+            let value: Value = serde_json::from_str(&format_json(&info)).unwrap();
+            assert_eq!(value["timestamp"], 1_000_000_000);
+            assert_eq!(value["method"], "GET");
+            assert_eq!(value["uri"], "/foo?bar=baz");
+            assert_eq!(value["version"], "HTTP/1.1");
+            assert_eq!(value["status"], 200);
+            assert_eq!(value["body_size"], 42);
+            assert_eq!(value["remote_addr"], Value::Null);
+            assert_eq!(value["user_agent"], "curl/7.61.0");
+        });
+    }
+
+    #[test]
+    fn test_format_remote_addr() {
+        let remote_addr = "192.0.2.1:54321".parse().ok();
+        with_info(remote_addr, |info| {
+            assert!(format_common(&info).starts_with("192.0.2.1 - - "));
+            let value: Value = serde_json::from_str(&format_json(&info)).unwrap();
+            assert_eq!(value["remote_addr"], "192.0.2.1");
+        });
+    }
+}
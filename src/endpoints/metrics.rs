@@ -0,0 +1,525 @@
+//! Components for collecting the metrics of HTTP requests.
+//!
+//! The collected metrics are stored in a `Registry` in the process, and are
+//! exposed in the [Prometheus text format] by the endpoint created with
+//! `metrics_endpoint()`.
+//!
+//! [Prometheus text format]: https://prometheus.io/docs/instrumenting/exposition_formats/
+//!
+//! # Example
+//!
+//! ```
+//! # use finchers::prelude::*;
+//! # use finchers::path;
+//! use finchers::endpoints::metrics::{instrument, metrics_endpoint, Registry};
+//!
+//! let registry = Registry::new();
+//!
+//! let hello = path!(@get / "hello")
+//!     .map(|| "Hello")
+//!     .wrap(instrument(&registry, "hello"));
+//!
+//! let metrics = path!(@get / "metrics").and(metrics_endpoint(&registry));
+//!
+//! let endpoint = hello.or(metrics);
+//! # drop(endpoint);
+//! ```
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::pin::PinMut;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+
+use futures_core::future::{Future, TryFuture};
+use futures_core::task;
+use futures_core::task::Poll;
+use futures_util::future::{ready, Ready};
+use http::header::HeaderValue;
+use http::{header, Method, Response, StatusCode};
+use pin_utils::{unsafe_pinned, unsafe_unpinned};
+
+use crate::endpoint::wrapper::Wrapper;
+use crate::endpoint::{Context, Endpoint, EndpointResult};
+use crate::error::{Error, Never};
+use crate::input::with_get_cx;
+use crate::output::payload::{Once, Payload};
+use crate::output::{Output, OutputContext};
+
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+const SIZE_BUCKETS: &[f64] = &[
+    100.0,
+    1_000.0,
+    10_000.0,
+    100_000.0,
+    1_000_000.0,
+    10_000_000.0,
+];
+
+/// A registry which stores the collected metrics.
+///
+/// The values of this type are cheaply cloneable and share the same storage.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    requests: BTreeMap<Labels, u64>,
+    in_flight: BTreeMap<(&'static str, Cow<'static, str>), i64>,
+    durations: BTreeMap<Labels, Histogram>,
+    sizes: BTreeMap<Labels, Histogram>,
+}
+
+/// (method, route, status class)
+type Labels = (&'static str, Cow<'static, str>, &'static str);
+
+#[derive(Debug)]
+struct Histogram {
+    buckets: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(buckets: &'static [f64]) -> Histogram {
+        Histogram {
+            buckets,
+            counts: vec![0; buckets.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.buckets.iter().zip(&mut self.counts) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+impl Registry {
+    /// Creates an empty registry.
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().expect("the metrics registry is poisoned")
+    }
+
+    fn start(&self, method: &Method, route: &Cow<'static, str>) {
+        let mut inner = self.lock();
+        *inner
+            .in_flight
+            .entry((method_label(method), route.clone()))
+            .or_insert(0) += 1;
+    }
+
+    fn finish(
+        &self,
+        method: &Method,
+        route: &Cow<'static, str>,
+        status: Option<StatusCode>,
+        elapsed: f64,
+        body_size: Option<u64>,
+    ) {
+        let mut inner = self.lock();
+        if let Some(n) = inner
+            .in_flight
+            .get_mut(&(method_label(method), route.clone()))
+        {
+            *n -= 1;
+        }
+
+        let status = match status {
+            Some(status) => status_class(status),
+            None => return,
+        };
+        let labels = (method_label(method), route.clone(), status);
+        *inner.requests.entry(labels.clone()).or_insert(0) += 1;
+        inner
+            .durations
+            .entry(labels.clone())
+            .or_insert_with(|| Histogram::new(DURATION_BUCKETS))
+            .observe(elapsed);
+        if let Some(size) = body_size {
+            inner
+                .sizes
+                .entry(labels)
+                .or_insert_with(|| Histogram::new(SIZE_BUCKETS))
+                .observe(size as f64);
+        }
+    }
+
+    /// Renders the collected metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let inner = self.lock();
+        let mut out = String::new();
+
+        out += "# HELP http_requests_total The total number of HTTP requests.\n";
+        out += "# TYPE http_requests_total counter\n";
+        for ((method, route, status), count) in &inner.requests {
+            let _ = writeln!(
+                out,
+                "http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                method,
+                escape_label(route),
+                status,
+                count
+            );
+        }
+
+        out += "# HELP http_requests_in_flight The number of HTTP requests being processed.\n";
+        out += "# TYPE http_requests_in_flight gauge\n";
+        for ((method, route), count) in &inner.in_flight {
+            let _ = writeln!(
+                out,
+                "http_requests_in_flight{{method=\"{}\",route=\"{}\"}} {}",
+                method,
+                escape_label(route),
+                count
+            );
+        }
+
+        render_histograms(
+            &mut out,
+            "http_request_duration_seconds",
+            "The latency of HTTP requests in seconds.",
+            &inner.durations,
+        );
+        render_histograms(
+            &mut out,
+            "http_response_size_bytes",
+            "The size of HTTP response bodies in bytes.",
+            &inner.sizes,
+        );
+
+        out
+    }
+}
+
+fn render_histograms(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    histograms: &BTreeMap<Labels, Histogram>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    for ((method, route, status), histogram) in histograms {
+        let labels = format!(
+            "method=\"{}\",route=\"{}\",status=\"{}\"",
+            method,
+            escape_label(route),
+            status
+        );
+        for (bound, count) in histogram.buckets.iter().zip(&histogram.counts) {
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{},le=\"{}\"}} {}",
+                name, labels, bound, count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{},le=\"+Inf\"}} {}",
+            name, labels, histogram.count
+        );
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, histogram.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, histogram.count);
+    }
+}
+
+/// Returns the label of the request method.
+///
+/// The extension methods are collapsed into `"other"`, so that the clients
+/// cannot create an unbounded number of series.
+fn method_label(method: &Method) -> &'static str {
+    const STANDARD_METHODS: &[&str] = &[
+        "GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH",
+    ];
+    STANDARD_METHODS
+        .iter()
+        .find(|&&m| m == method.as_str())
+        .cloned()
+        .unwrap_or("other")
+}
+
+fn status_class(status: StatusCode) -> &'static str {
+    match status.as_u16() / 100 {
+        1 => "1xx",
+        2 => "2xx",
+        3 => "3xx",
+        4 => "4xx",
+        _ => "5xx",
+    }
+}
+
+fn escape_label(value: &str) -> Cow<'_, str> {
+    if value.contains(|c| c == '\\' || c == '"' || c == '\n') {
+        Cow::Owned(
+            value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n"),
+        )
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+// ==== Instrument ====
+
+/// Create a wrapper for creating an endpoint which records the metrics of
+/// the requests into the specified registry.
+///
+/// The value of `route` is used as the label which distinguishes the endpoints.
+pub fn instrument(registry: &Registry, route: impl Into<Cow<'static, str>>) -> Instrument {
+    Instrument {
+        registry: registry.clone(),
+        route: route.into(),
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Clone)]
+pub struct Instrument {
+    registry: Registry,
+    route: Cow<'static, str>,
+}
+
+impl<'a, E> Wrapper<'a, E> for Instrument
+where
+    E: Endpoint<'a>,
+    E::Output: Output,
+{
+    type Output = (InstrumentedResponse<<E::Output as Output>::Body>,);
+    type Endpoint = InstrumentEndpoint<E>;
+
+    fn wrap(self, endpoint: E) -> Self::Endpoint {
+        InstrumentEndpoint {
+            endpoint,
+            instrument: self,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InstrumentEndpoint<E> {
+    endpoint: E,
+    instrument: Instrument,
+}
+
+impl<'a, E> Endpoint<'a> for InstrumentEndpoint<E>
+where
+    E: Endpoint<'a>,
+    E::Output: Output,
+{
+    type Output = (InstrumentedResponse<<E::Output as Output>::Body>,);
+    type Future = InstrumentFuture<'a, E::Future>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        // The requests which do not match to the inner endpoint are not recorded.
+        let future = self.endpoint.apply(cx)?;
+        let method = cx.input().method().clone();
+        self.instrument
+            .registry
+            .start(&method, &self.instrument.route);
+        Ok(InstrumentFuture {
+            future,
+            guard: Some(InFlight {
+                instrument: &self.instrument,
+                method,
+                start: Instant::now(),
+                finished: false,
+            }),
+        })
+    }
+}
+
+/// A guard which keeps the in-flight gauge consistent when the future is
+/// dropped before completion.
+#[derive(Debug)]
+struct InFlight<'a> {
+    instrument: &'a Instrument,
+    method: Method,
+    start: Instant,
+    finished: bool,
+}
+
+impl<'a> InFlight<'a> {
+    fn finish(mut self, status: StatusCode, body_size: Option<u64>) {
+        let elapsed = self.start.elapsed();
+        let elapsed = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9;
+        self.instrument.registry.finish(
+            &self.method,
+            &self.instrument.route,
+            Some(status),
+            elapsed,
+            body_size,
+        );
+        self.finished = true;
+    }
+}
+
+impl<'a> Drop for InFlight<'a> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.instrument
+                .registry
+                .finish(&self.method, &self.instrument.route, None, 0.0, None);
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InstrumentFuture<'a, Fut> {
+    future: Fut,
+    guard: Option<InFlight<'a>>,
+}
+
+impl<'a, Fut> InstrumentFuture<'a, Fut> {
+    unsafe_pinned!(future: Fut);
+    unsafe_unpinned!(guard: Option<InFlight<'a>>);
+}
+
+impl<'a, Fut> Future for InstrumentFuture<'a, Fut>
+where
+    Fut: TryFuture<Error = Error>,
+    Fut::Ok: Output,
+{
+    type Output = Result<(InstrumentedResponse<<Fut::Ok as Output>::Body>,), Error>;
+
+    fn poll(mut self: PinMut<'_, Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let result = match self.future().try_poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(result) => result.and_then(|x| {
+                with_get_cx(|input| {
+                    let mut ocx = OutputContext::new(input);
+                    x.respond(&mut ocx).map_err(Into::into)
+                })
+            }),
+        };
+
+        let guard = self.guard().take().expect("the future has already polled");
+        match result {
+            Ok(response) => {
+                guard.finish(response.status(), response.body().content_length());
+                Poll::Ready(Ok((InstrumentedResponse(response),)))
+            }
+            Err(err) => {
+                guard.finish(err.status_code(), None);
+                Poll::Ready(Err(err))
+            }
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InstrumentedResponse<Bd>(Response<Bd>);
+
+impl<Bd: Payload> Output for InstrumentedResponse<Bd> {
+    type Body = Bd;
+    type Error = Never;
+
+    #[inline(always)]
+    fn respond(self, _: &mut OutputContext<'_>) -> Result<Response<Self::Body>, Self::Error> {
+        Ok(self.0)
+    }
+}
+
+// ==== MetricsEndpoint ====
+
+/// Create an endpoint which renders the metrics stored in the registry
+/// in the Prometheus text format.
+pub fn metrics_endpoint(registry: &Registry) -> MetricsEndpoint {
+    (MetricsEndpoint {
+        registry: registry.clone(),
+    })
+    .with_output::<(Response<Once<String>>,)>()
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Clone)]
+pub struct MetricsEndpoint {
+    registry: Registry,
+}
+
+impl<'a> Endpoint<'a> for MetricsEndpoint {
+    type Output = (Response<Once<String>>,);
+    type Future = Ready<Result<Self::Output, Error>>;
+
+    fn apply(&'a self, _: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let mut response = Response::new(Once::new(self.registry.render()));
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"),
+        );
+        Ok(ready(Ok((response,))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let mut histogram = Histogram::new(&[1.0, 2.0]);
+        histogram.observe(0.5);
+        histogram.observe(1.5);
+        histogram.observe(3.0);
+        assert_eq!(histogram.counts, vec![1, 2]);
+        assert_eq!(histogram.count, 3);
+        assert!((histogram.sum - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_render() {
+        let registry = Registry::new();
+        let route = Cow::Borrowed("hello");
+        registry.start(&Method::GET, &route);
+        registry.finish(&Method::GET, &route, Some(StatusCode::OK), 0.02, Some(5));
+
+        let rendered = registry.render();
+        assert!(rendered
+            .contains("http_requests_total{method=\"GET\",route=\"hello\",status=\"2xx\"} 1\n"));
+        assert!(rendered.contains("http_requests_in_flight{method=\"GET\",route=\"hello\"} 0\n"));
+        assert!(rendered.contains(
+            "http_request_duration_seconds_bucket{method=\"GET\",route=\"hello\",status=\"2xx\",le=\"0.025\"} 1\n"
+        ));
+        assert!(rendered.contains(
+            "http_response_size_bytes_sum{method=\"GET\",route=\"hello\",status=\"2xx\"} 5\n"
+        ));
+    }
+
+    #[test]
+    fn test_extension_methods() {
+        let registry = Registry::new();
+        let route = Cow::Borrowed("hello");
+        for method in &["PURGE", "FOO", "BAR"] {
+            let method = Method::from_bytes(method.as_bytes()).unwrap();
+            registry.start(&method, &route);
+            registry.finish(&method, &route, Some(StatusCode::OK), 0.02, None);
+        }
+
+        let rendered = registry.render();
+        assert!(rendered
+            .contains("http_requests_total{method=\"other\",route=\"hello\",status=\"2xx\"} 3\n"));
+        assert!(!rendered.contains("PURGE"));
+    }
+}
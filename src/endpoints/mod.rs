@@ -1,9 +1,16 @@
 //! Built-in endpoints.
 
+pub mod auth;
 pub mod body;
+pub mod cache;
+pub mod concurrency_limit;
 pub mod cookie;
+#[cfg(feature = "secure")]
+pub mod csrf;
 pub mod fs;
 pub mod header;
+#[cfg(feature = "jwt")]
+pub mod jwt;
 pub mod logging;
 #[doc(hidden)]
 #[deprecated(
@@ -11,6 +18,8 @@ pub mod logging;
     note = "use components in `endpoint::syntax` instead"
 )]
 pub mod method;
+pub mod metrics;
+pub mod normalize_path;
 
 #[doc(hidden)]
 #[deprecated(
@@ -18,4 +27,18 @@ pub mod method;
     note = "use components in `endpoint::syntax` instead"
 )]
 pub mod path;
+pub mod proxy;
 pub mod query;
+pub mod rate_limit;
+pub mod request_id;
+pub mod session;
+pub mod timeout;
+pub mod tracing;
+
+mod ext;
+mod remote_addr;
+mod state;
+
+pub use self::ext::{ext, Ext};
+pub use self::remote_addr::{remote_addr, RemoteAddr};
+pub use self::state::{state, State};
@@ -0,0 +1,274 @@
+//! Components for canonicalizing the request path before routing.
+//!
+//! The wrapper collapses the duplicate slashes (e.g. `/users//42`) and resolves
+//! the dot segments (e.g. `/users/./42` or `/static/../users/42`) in the remaining
+//! path, and then handles the trailing slash according to the configured policy.
+//! The request URI is rewritten to the canonical form, so the wrapper should be
+//! applied to the top-level endpoint.
+//!
+//! # Example
+//!
+//! ```
+//! # use finchers::prelude::*;
+//! # use finchers::path;
+//! use finchers::endpoints::normalize_path::{normalize_path, TrailingSlash};
+//!
+//! let endpoint = path!(@get / "users" / u32 /)
+//!     .map(|id: u32| format!("user {}", id))
+//!     .wrap(normalize_path().trailing_slash(TrailingSlash::Redirect));
+//! # drop(endpoint);
+//! ```
+
+use std::fmt;
+
+use http::header::{HeaderMap, HeaderValue};
+use http::uri::{PathAndQuery, Uri};
+use http::{header, StatusCode};
+
+use crate::endpoint::wrapper::Wrapper;
+use crate::endpoint::{Context, Endpoint, EndpointError, EndpointResult};
+use crate::error::{bad_request, HttpError};
+
+/// The policy for handling the trailing slash in the request path.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TrailingSlash {
+    /// The paths with and without the trailing slash are routed in the same way.
+    Ignore,
+
+    /// The paths with the trailing slash are not matched.
+    Strict,
+
+    /// The requests to the non-canonical paths, including the ones with the
+    /// trailing slash, are redirected to the canonical form with `308 Permanent Redirect`.
+    Redirect,
+}
+
+/// Create a wrapper for creating an endpoint which canonicalizes the request path.
+///
+/// By default, the duplicate slashes and the dot segments are removed,
+/// and the trailing slash is ignored.
+pub fn normalize_path() -> NormalizePath {
+    NormalizePath {
+        trailing_slash: TrailingSlash::Ignore,
+        merge_slashes: true,
+        resolve_dots: true,
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone)]
+pub struct NormalizePath {
+    trailing_slash: TrailingSlash,
+    merge_slashes: bool,
+    resolve_dots: bool,
+}
+
+impl NormalizePath {
+    /// Sets the policy for handling the trailing slash.
+    pub fn trailing_slash(self, trailing_slash: TrailingSlash) -> NormalizePath {
+        NormalizePath {
+            trailing_slash,
+            ..self
+        }
+    }
+
+    /// Sets whether to collapse the duplicate slashes into a single slash.
+    pub fn merge_slashes(self, enabled: bool) -> NormalizePath {
+        NormalizePath {
+            merge_slashes: enabled,
+            ..self
+        }
+    }
+
+    /// Sets whether to resolve the dot segments (`.` and `..`).
+    pub fn resolve_dots(self, enabled: bool) -> NormalizePath {
+        NormalizePath {
+            resolve_dots: enabled,
+            ..self
+        }
+    }
+
+    /// Returns the canonical form of `path`, without the trailing slash.
+    fn canonicalize(&self, path: &str) -> String {
+        let path = if path.ends_with('/') {
+            &path[..path.len() - 1]
+        } else {
+            path
+        };
+
+        let mut segments = vec![];
+        for segment in path.split('/') {
+            if segment.is_empty() && self.merge_slashes {
+                continue;
+            }
+            if self.resolve_dots {
+                match decode_dots(segment) {
+                    Some(1) => continue,
+                    Some(2) => {
+                        segments.pop();
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            segments.push(segment);
+        }
+        segments.join("/")
+    }
+}
+
+/// Returns the number of dots if the segment is `.` or `..`, including the percent-encoded forms.
+fn decode_dots(segment: &str) -> Option<usize> {
+    let mut rest = segment;
+    let mut n = 0;
+    while !rest.is_empty() {
+        if rest.starts_with('.') {
+            rest = &rest[1..];
+        } else if rest.len() >= 3 && rest.as_bytes()[..3].eq_ignore_ascii_case(b"%2e") {
+            rest = &rest[3..];
+        } else {
+            return None;
+        }
+        n += 1;
+    }
+    match n {
+        1 | 2 => Some(n),
+        _ => None,
+    }
+}
+
+impl<'a, E> Wrapper<'a, E> for NormalizePath
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Endpoint = NormalizePathEndpoint<E>;
+
+    fn wrap(self, endpoint: E) -> Self::Endpoint {
+        NormalizePathEndpoint {
+            endpoint,
+            config: self,
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone)]
+pub struct NormalizePathEndpoint<E> {
+    endpoint: E,
+    config: NormalizePath,
+}
+
+impl<'a, E> Endpoint<'a> for NormalizePathEndpoint<E>
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Future = E::Future;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let pos = cx.current_cursor().pos;
+        let (has_trailing_slash, canonical) = {
+            let input = cx.input();
+            let path = input.uri().path();
+            let (prefix, remaining) = path.split_at(pos.min(path.len()));
+
+            let canonical = self.config.canonicalize(remaining);
+            let canonical = if canonical != remaining {
+                let mut path_and_query = format!("{}{}", prefix, canonical);
+                if let Some(query) = input.uri().query() {
+                    path_and_query += "?";
+                    path_and_query += query;
+                }
+                Some(path_and_query)
+            } else {
+                None
+            };
+            (remaining.ends_with('/'), canonical)
+        };
+
+        if self.config.trailing_slash == TrailingSlash::Strict && has_trailing_slash {
+            return Err(EndpointError::not_matched());
+        }
+
+        if let Some(path_and_query) = canonical {
+            if self.config.trailing_slash == TrailingSlash::Redirect {
+                return Err(EndpointError::custom(PathRedirect {
+                    location: path_and_query,
+                }));
+            }
+            let original = cx.input().uri().clone();
+            let uri = rewrite_path(original.clone(), &path_and_query)
+                .map_err(|err| EndpointError::custom(bad_request(err)))?;
+            cx.input().set_uri(uri);
+
+            // Restore the original URI so that the other routes see the request as it is.
+            let result = self.endpoint.apply(cx);
+            if result.is_err() {
+                cx.input().set_uri(original);
+            }
+            return result;
+        }
+
+        self.endpoint.apply(cx)
+    }
+}
+
+fn rewrite_path(uri: Uri, path_and_query: &str) -> Result<Uri, http::Error> {
+    let mut parts = uri.into_parts();
+    parts.path_and_query = Some(path_and_query.parse::<PathAndQuery>()?);
+    Ok(Uri::from_parts(parts)?)
+}
+
+/// An error which redirects the client to the canonical path.
+#[derive(Debug)]
+pub struct PathRedirect {
+    location: String,
+}
+
+impl PathRedirect {
+    /// Returns the canonical location of the requested resource.
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+}
+
+impl fmt::Display for PathRedirect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the resource is located at {}", self.location)
+    }
+}
+
+impl HttpError for PathRedirect {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::PERMANENT_REDIRECT
+    }
+
+    fn headers(&self, headers: &mut HeaderMap) {
+        if let Ok(location) = HeaderValue::from_str(&self.location) {
+            headers.insert(header::LOCATION, location);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonicalize() {
+        let config = normalize_path();
+        assert_eq!(config.canonicalize(""), "");
+        assert_eq!(config.canonicalize("users/42"), "users/42");
+        assert_eq!(config.canonicalize("users/42/"), "users/42");
+        assert_eq!(config.canonicalize("users//42"), "users/42");
+        assert_eq!(config.canonicalize("users/./42"), "users/42");
+        assert_eq!(config.canonicalize("static/../users/42"), "users/42");
+        assert_eq!(config.canonicalize("static/%2E%2e/users/42"), "users/42");
+        assert_eq!(config.canonicalize("../../users"), "users");
+        assert_eq!(config.canonicalize("users/.../42"), "users/.../42");
+
+        let config = normalize_path().merge_slashes(false).resolve_dots(false);
+        assert_eq!(config.canonicalize("users//./42/"), "users//./42");
+    }
+}
@@ -0,0 +1,429 @@
+//! Components for handling the requests forwarded by reverse proxies.
+//!
+//! When the peer of the connection is one of the trusted proxies, the wrapper
+//! reads the `Forwarded` header (or `X-Forwarded-For`, `X-Forwarded-Proto`
+//! and `X-Forwarded-Host` if `Forwarded` is missing) and overrides the values
+//! returned from `Input::remote_addr()`, `Input::scheme()` and `Input::host()`
+//! with the client-facing ones.
+//!
+//! The addresses in the forwarding chain are examined from the nearest one, and
+//! the first address which is not trusted is considered as the client.
+//! The headers sent from untrusted peers are ignored.
+//!
+//! # Example
+//!
+//! ```
+//! # use finchers::prelude::*;
+//! # use finchers::path;
+//! use finchers::endpoints::proxy::{trusted_proxies, Cidr};
+//! use std::net::SocketAddr;
+//!
+//! let cidrs: Vec<Cidr> = vec!["10.0.0.0/8".parse().unwrap(), "::1".parse().unwrap()];
+//!
+//! let endpoint = path!(@get / "ip")
+//!     .and(endpoints::remote_addr())
+//!     .map(|addr: Option<SocketAddr>| format!("{:?}", addr))
+//!     .wrap(trusted_proxies(cidrs));
+//! # drop(endpoint);
+//! ```
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+
+use failure::Fail;
+use http::header::{HeaderMap, HeaderName};
+use http::uri::Authority;
+
+use crate::endpoint::wrapper::Wrapper;
+use crate::endpoint::{Context, Endpoint, EndpointResult};
+use crate::input::{Forwarded, Input};
+
+// ==== Cidr ====
+
+/// A range of IP addresses represented in CIDR notation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// Create a new `Cidr` from the network address and the length of prefix.
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<Cidr, CidrParseError> {
+        let max = match addr {
+            IpAddr::V4(..) => 32,
+            IpAddr::V6(..) => 128,
+        };
+        if prefix > max {
+            return Err(CidrParseError { _priv: () });
+        }
+        Ok(Cidr { addr, prefix })
+    }
+
+    /// Returns whether the specified address is contained in this range.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match (self.addr, addr) {
+            (IpAddr::V4(net), IpAddr::V4(addr)) => {
+                prefix_matches(&net.octets(), &addr.octets(), self.prefix)
+            }
+            (IpAddr::V6(net), IpAddr::V6(addr)) => {
+                prefix_matches(&net.octets(), &addr.octets(), self.prefix)
+            }
+            (IpAddr::V4(..), IpAddr::V6(addr)) => match ipv4_mapped(&addr) {
+                Some(addr) => self.contains(IpAddr::V4(addr)),
+                None => false,
+            },
+            (IpAddr::V6(..), IpAddr::V4(..)) => false,
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = CidrParseError;
+
+    fn from_str(s: &str) -> Result<Cidr, Self::Err> {
+        let mut parts = s.splitn(2, '/');
+        let addr: IpAddr = parts
+            .next()
+            .unwrap()
+            .parse()
+            .map_err(|_| CidrParseError { _priv: () })?;
+        let prefix = match parts.next() {
+            Some(prefix) => prefix.parse().map_err(|_| CidrParseError { _priv: () })?,
+            None if addr.is_ipv4() => 32,
+            None => 128,
+        };
+        Cidr::new(addr, prefix)
+    }
+}
+
+fn prefix_matches(net: &[u8], addr: &[u8], prefix: u8) -> bool {
+    let n = (prefix / 8) as usize;
+    let rem = prefix % 8;
+    net[..n] == addr[..n]
+        && (rem == 0 || {
+            let mask = !0u8 << (8 - rem);
+            net[n] & mask == addr[n] & mask
+        })
+}
+
+fn ipv4_mapped(addr: &Ipv6Addr) -> Option<Ipv4Addr> {
+    match addr.segments() {
+        [0, 0, 0, 0, 0, 0xffff, hi, lo] => Some(Ipv4Addr::new(
+            (hi >> 8) as u8,
+            hi as u8,
+            (lo >> 8) as u8,
+            lo as u8,
+        )),
+        _ => None,
+    }
+}
+
+/// An error which will be returned when parsing an invalid CIDR notation.
+#[derive(Debug, Fail)]
+#[fail(display = "invalid CIDR notation")]
+pub struct CidrParseError {
+    _priv: (),
+}
+
+// ==== TrustedProxies ====
+
+/// Create a wrapper for creating an endpoint which trusts the forwarding headers
+/// sent from the proxies in the specified ranges.
+pub fn trusted_proxies<I>(cidrs: I) -> TrustedProxies
+where
+    I: IntoIterator<Item = Cidr>,
+{
+    TrustedProxies {
+        cidrs: cidrs.into_iter().collect(),
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Clone)]
+pub struct TrustedProxies {
+    cidrs: Vec<Cidr>,
+}
+
+impl TrustedProxies {
+    fn is_trusted(&self, addr: IpAddr) -> bool {
+        self.cidrs.iter().any(|cidr| cidr.contains(addr))
+    }
+
+    fn forwarded(&self, input: &Input) -> Option<Forwarded> {
+        let peer = input.connection()?.remote_addr()?;
+        if !self.is_trusted(peer.ip()) {
+            return None;
+        }
+
+        let hops = if input.headers().contains_key("forwarded") {
+            parse_forwarded(input.headers())
+        } else {
+            parse_x_forwarded(input.headers())
+        };
+
+        // Find the nearest hop which is not trusted.
+        let mut client = None;
+        for hop in hops.into_iter().rev() {
+            let trusted = hop.addr.map_or(false, |addr| self.is_trusted(addr.ip()));
+            client = Some(hop);
+            if !trusted {
+                break;
+            }
+        }
+
+        client.map(|hop| Forwarded {
+            remote_addr: hop.addr,
+            scheme: hop.proto,
+            host: hop.host,
+        })
+    }
+}
+
+impl<'a, E> Wrapper<'a, E> for TrustedProxies
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Endpoint = TrustedProxiesEndpoint<E>;
+
+    fn wrap(self, endpoint: E) -> Self::Endpoint {
+        TrustedProxiesEndpoint {
+            endpoint,
+            proxies: self,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct TrustedProxiesEndpoint<E> {
+    endpoint: E,
+    proxies: TrustedProxies,
+}
+
+impl<'a, E> Endpoint<'a> for TrustedProxiesEndpoint<E>
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Future = E::Future;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let forwarded = self.proxies.forwarded(&*cx.input());
+        if let Some(forwarded) = forwarded {
+            cx.input().extensions_mut().insert(forwarded);
+        }
+        self.endpoint.apply(cx)
+    }
+}
+
+// ==== parsers ====
+
+#[derive(Debug, Default, PartialEq)]
+struct Hop {
+    addr: Option<SocketAddr>,
+    proto: Option<String>,
+    host: Option<String>,
+}
+
+/// Parses the `Forwarded` header fields defined in RFC 7239.
+fn parse_forwarded(headers: &HeaderMap) -> Vec<Hop> {
+    let mut hops = vec![];
+    for value in headers.get_all("forwarded") {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(..) => continue,
+        };
+        for element in split_quoted(value, ',') {
+            let mut hop = Hop::default();
+            for pair in split_quoted(element, ';') {
+                let mut pair = pair.splitn(2, '=');
+                let name = pair.next().unwrap().trim();
+                let value = unquote(pair.next().unwrap_or("").trim());
+                if name.eq_ignore_ascii_case("for") {
+                    hop.addr = parse_node(value);
+                } else if name.eq_ignore_ascii_case("proto") {
+                    hop.proto = parse_proto(value);
+                } else if name.eq_ignore_ascii_case("host") {
+                    hop.host = parse_host(value);
+                }
+            }
+            hops.push(hop);
+        }
+    }
+    hops
+}
+
+/// Parses the `X-Forwarded-For`, `X-Forwarded-Proto` and `X-Forwarded-Host` header fields.
+fn parse_x_forwarded(headers: &HeaderMap) -> Vec<Hop> {
+    fn values<'a>(headers: &'a HeaderMap, name: &'static str) -> Vec<&'a str> {
+        headers
+            .get_all(HeaderName::from_static(name))
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect()
+    }
+
+    let addrs = values(headers, "x-forwarded-for");
+    let protos = values(headers, "x-forwarded-proto");
+    let hosts = values(headers, "x-forwarded-host");
+
+    // The values of `X-Forwarded-Proto` and `X-Forwarded-Host` are associated with
+    // the corresponding hops only if the lengths of lists are equal.
+    // Otherwise, the last values are used for all hops.
+    let pick = |values: &[&'_ str], i: usize| -> Option<String> {
+        if values.len() == addrs.len() {
+            values.get(i).cloned().map(ToOwned::to_owned)
+        } else {
+            values.last().cloned().map(ToOwned::to_owned)
+        }
+    };
+
+    addrs
+        .iter()
+        .enumerate()
+        .map(|(i, addr)| Hop {
+            addr: parse_node(addr),
+            proto: pick(&protos, i).and_then(|proto| parse_proto(&proto)),
+            host: pick(&hosts, i).and_then(|host| parse_host(&host)),
+        })
+        .collect()
+}
+
+fn split_quoted(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c == sep && !quoted => {
+                parts.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(s[start..].trim());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+fn unquote(s: &str) -> &str {
+    if s.len() >= 2 && s.starts_with('"') && s.ends_with('"') {
+        &s[1..s.len() - 1]
+    } else {
+        s
+    }
+}
+
+/// Parses a node identifier, e.g. `192.0.2.43`, `192.0.2.43:4711` or `[2001:db8::1]:4711`.
+///
+/// The obfuscated identifiers and `unknown` are treated as missing.
+/// The port number is zero if it is not specified.
+fn parse_node(s: &str) -> Option<SocketAddr> {
+    if let Ok(addr) = s.parse::<SocketAddr>() {
+        return Some(addr);
+    }
+    let s = if s.starts_with('[') && s.ends_with(']') {
+        &s[1..s.len() - 1]
+    } else {
+        s
+    };
+    s.parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, 0))
+}
+
+fn parse_proto(s: &str) -> Option<String> {
+    match s.to_ascii_lowercase().as_str() {
+        scheme @ "http" | scheme @ "https" => Some(scheme.to_owned()),
+        _ => None,
+    }
+}
+
+fn parse_host(s: &str) -> Option<String> {
+    s.parse::<Authority>()
+        .ok()
+        .map(|host| host.as_str().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::header::HeaderValue;
+
+    #[test]
+    fn test_cidr() {
+        let cidr: Cidr = "10.0.0.0/8".parse().unwrap();
+        assert!(cidr.contains("10.1.2.3".parse().unwrap()));
+        assert!(!cidr.contains("11.0.0.1".parse().unwrap()));
+        assert!(cidr.contains("::ffff:10.0.0.1".parse().unwrap()));
+
+        let cidr: Cidr = "192.168.1.128/25".parse().unwrap();
+        assert!(cidr.contains("192.168.1.200".parse().unwrap()));
+        assert!(!cidr.contains("192.168.1.100".parse().unwrap()));
+
+        let cidr: Cidr = "::1".parse().unwrap();
+        assert!(cidr.contains("::1".parse().unwrap()));
+        assert!(!cidr.contains("127.0.0.1".parse().unwrap()));
+
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("localhost".parse::<Cidr>().is_err());
+    }
+
+    #[test]
+    fn test_parse_forwarded() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            "forwarded",
+            HeaderValue::from_static(
+                r#"for=192.0.2.43;proto=https;host=example.com, for="[2001:db8::1]:4711""#,
+            ),
+        );
+        headers.append("forwarded", HeaderValue::from_static("for=unknown"));
+
+        assert_eq!(
+            parse_forwarded(&headers),
+            vec![
+                Hop {
+                    addr: Some("192.0.2.43:0".parse().unwrap()),
+                    proto: Some("https".into()),
+                    host: Some("example.com".into()),
+                },
+                Hop {
+                    addr: Some("[2001:db8::1]:4711".parse().unwrap()),
+                    ..Default::default()
+                },
+                Hop::default(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_x_forwarded() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("192.0.2.43, 10.0.0.1"),
+        );
+        headers.insert("x-forwarded-proto", HeaderValue::from_static("https"));
+
+        assert_eq!(
+            parse_x_forwarded(&headers),
+            vec![
+                Hop {
+                    addr: Some("192.0.2.43:0".parse().unwrap()),
+                    proto: Some("https".into()),
+                    host: None,
+                },
+                Hop {
+                    addr: Some("10.0.0.1:0".parse().unwrap()),
+                    proto: Some("https".into()),
+                    host: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,406 @@
+//! Components for limiting the rate of requests.
+//!
+//! The limit is enforced with the Generic Cell Rate Algorithm (GCRA), which
+//! behaves like a token bucket refilled at a constant rate: a client can
+//! send up to `limit` requests at once, and then one request per `period / limit`.
+//!
+//! # Example
+//!
+//! ```
+//! # use finchers::prelude::*;
+//! # use finchers::path;
+//! use finchers::endpoints::rate_limit::{rate_limit, Key, Quota};
+//!
+//! let login = path!(@post / "login" /)
+//!     .map(|| "logged in")
+//!     .wrap(rate_limit(Quota::per_minute(5), Key::remote_addr()));
+//! # drop(login);
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use http::{header, StatusCode};
+
+use crate::endpoint::wrapper::Wrapper;
+use crate::endpoint::{Context, Endpoint, EndpointError, EndpointResult};
+use crate::error::{Error, HttpError};
+use crate::input::Input;
+
+// ==== Quota ====
+
+/// The maximum number of requests permitted in a period.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Quota {
+    limit: u32,
+    period: Duration,
+}
+
+impl Quota {
+    /// Create a new `Quota` which permits `limit` requests per `period`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `limit` is zero.
+    pub fn new(limit: u32, period: Duration) -> Quota {
+        assert!(limit > 0, "the limit must be greater than zero");
+        Quota { limit, period }
+    }
+
+    /// Create a new `Quota` which permits `limit` requests per second.
+    pub fn per_second(limit: u32) -> Quota {
+        Quota::new(limit, Duration::from_secs(1))
+    }
+
+    /// Create a new `Quota` which permits `limit` requests per minute.
+    pub fn per_minute(limit: u32) -> Quota {
+        Quota::new(limit, Duration::from_secs(60))
+    }
+
+    /// Create a new `Quota` which permits `limit` requests per hour.
+    pub fn per_hour(limit: u32) -> Quota {
+        Quota::new(limit, Duration::from_secs(60 * 60))
+    }
+
+    fn period_millis(&self) -> u64 {
+        self.period.as_secs() * 1000 + u64::from(self.period.subsec_millis())
+    }
+
+    /// Applies a step of GCRA.
+    ///
+    /// `tat` is the theoretical arrival time of the next request and `now` is the current time,
+    /// both in milliseconds since the UNIX epoch.
+    /// It returns the new value of `tat` and whether the request is permitted.
+    fn check(&self, tat: Option<u64>, now: u64) -> (u64, Decision) {
+        let period = self.period_millis();
+        let interval = (period / u64::from(self.limit)).max(1);
+        let tat = tat.map_or(now, |tat| tat.max(now));
+        let new_tat = tat + interval;
+        let diff = new_tat - now;
+
+        if diff > period {
+            let decision = Decision::Rejected {
+                retry_after: div_ceil(diff - period, 1000),
+                reset: div_ceil(tat - now, 1000),
+            };
+            (tat, decision)
+        } else {
+            (new_tat, Decision::Permitted)
+        }
+    }
+}
+
+fn div_ceil(n: u64, d: u64) -> u64 {
+    (n + d - 1) / d
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Decision {
+    Permitted,
+    Rejected { retry_after: u64, reset: u64 },
+}
+
+// ==== RateLimitStore ====
+
+/// Trait representing a backend which stores the state of rate limiters.
+///
+/// The state associated with a key is the theoretical arrival time of the next
+/// request, represented in milliseconds since the UNIX epoch.
+/// The methods of this trait are called synchronously while the request is routed.
+pub trait RateLimitStore: Send + Sync + 'static {
+    /// Atomically replaces the state associated with `key` with the value
+    /// returned from `f`.
+    ///
+    /// The function receives the current state, or `None` if the key has no state.
+    fn update(&self, key: &str, f: &mut dyn FnMut(Option<u64>) -> u64) -> Result<(), Error>;
+}
+
+/// A `RateLimitStore` which keeps the states in the process memory.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    inner: Mutex<MemoryStoreInner>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryStoreInner {
+    states: HashMap<String, u64>,
+    updates: usize,
+}
+
+impl MemoryStore {
+    /// Create a new `MemoryStore`.
+    pub fn new() -> MemoryStore {
+        Default::default()
+    }
+}
+
+impl RateLimitStore for MemoryStore {
+    fn update(&self, key: &str, f: &mut dyn FnMut(Option<u64>) -> u64) -> Result<(), Error> {
+        let mut inner = self.inner.lock().expect("the rate limit store is poisoned");
+
+        // Periodically remove the states which no longer affect the decisions.
+        inner.updates += 1;
+        if inner.updates % 1024 == 0 {
+            let now = now_millis();
+            inner.states.retain(|_, tat| *tat > now);
+        }
+
+        let tat = f(inner.states.get(key).cloned());
+        inner.states.insert(key.to_owned(), tat);
+        Ok(())
+    }
+}
+
+fn now_millis() -> u64 {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_else(|_| Duration::from_secs(0));
+    elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis())
+}
+
+// ==== Key ====
+
+/// A strategy to determine which bucket a request is counted in.
+#[derive(Clone)]
+pub struct Key {
+    kind: KeyKind,
+}
+
+#[derive(Clone)]
+enum KeyKind {
+    RemoteAddr,
+    Header(HeaderName),
+    Custom(Arc<dyn Fn(&Input) -> Option<String> + Send + Sync + 'static>),
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            KeyKind::RemoteAddr => f.debug_tuple("RemoteAddr").finish(),
+            KeyKind::Header(ref name) => f.debug_tuple("Header").field(name).finish(),
+            KeyKind::Custom(..) => f.debug_tuple("Custom").finish(),
+        }
+    }
+}
+
+impl Key {
+    /// Create a `Key` which uses the IP address of the client.
+    pub fn remote_addr() -> Key {
+        Key {
+            kind: KeyKind::RemoteAddr,
+        }
+    }
+
+    /// Create a `Key` which uses the value of the specified header field.
+    pub fn header(name: HeaderName) -> Key {
+        Key {
+            kind: KeyKind::Header(name),
+        }
+    }
+
+    /// Create a `Key` which uses the value computed by the provided function.
+    pub fn custom<F>(f: F) -> Key
+    where
+        F: Fn(&Input) -> Option<String> + Send + Sync + 'static,
+    {
+        Key {
+            kind: KeyKind::Custom(Arc::new(f)),
+        }
+    }
+
+    fn extract(&self, input: &Input) -> Option<String> {
+        match self.kind {
+            KeyKind::RemoteAddr => input.remote_addr().map(|addr| addr.ip().to_string()),
+            KeyKind::Header(ref name) => input
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(ToOwned::to_owned),
+            KeyKind::Custom(ref f) => f(input),
+        }
+    }
+}
+
+// ==== RateLimit ====
+
+/// Create a wrapper for creating an endpoint which limits the rate of
+/// requests per key to the specified quota.
+///
+/// The requests from which the key cannot be extracted share a single bucket.
+/// A request exceeding the quota is rejected with `429 Too Many Requests`,
+/// carrying the `Retry-After` and `RateLimit-*` headers.
+pub fn rate_limit(quota: Quota, key: Key) -> RateLimit {
+    RateLimit {
+        quota,
+        key,
+        store: Arc::new(MemoryStore::new()),
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Clone)]
+pub struct RateLimit {
+    quota: Quota,
+    key: Key,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl fmt::Debug for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("quota", &self.quota)
+            .field("key", &self.key)
+            .finish()
+    }
+}
+
+impl RateLimit {
+    /// Sets the store which keeps the states of buckets.
+    ///
+    /// The default value is a `MemoryStore` owned by this wrapper.
+    pub fn store(self, store: impl RateLimitStore) -> RateLimit {
+        RateLimit {
+            store: Arc::new(store),
+            ..self
+        }
+    }
+
+    fn check(&self, input: &Input) -> Result<(), Error> {
+        let key = self.key.extract(input).unwrap_or_default();
+        let now = now_millis();
+        let mut decision = None;
+        self.store.update(&key, &mut |tat| {
+            let (tat, d) = self.quota.check(tat, now);
+            decision = Some(d);
+            tat
+        })?;
+
+        match decision.expect("the store did not call the update function") {
+            Decision::Permitted => Ok(()),
+            Decision::Rejected { retry_after, reset } => Err(TooManyRequests {
+                limit: self.quota.limit,
+                retry_after,
+                reset,
+            }
+            .into()),
+        }
+    }
+}
+
+impl<'a, E> Wrapper<'a, E> for RateLimit
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Endpoint = RateLimitEndpoint<E>;
+
+    fn wrap(self, endpoint: E) -> Self::Endpoint {
+        RateLimitEndpoint {
+            endpoint,
+            rate_limit: self,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct RateLimitEndpoint<E> {
+    endpoint: E,
+    rate_limit: RateLimit,
+}
+
+impl<'a, E> Endpoint<'a> for RateLimitEndpoint<E>
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Future = E::Future;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        // The requests which do not match to the inner endpoint are not counted.
+        let future = self.endpoint.apply(cx)?;
+        self.rate_limit
+            .check(&*cx.input())
+            .map_err(EndpointError::custom)?;
+        Ok(future)
+    }
+}
+
+/// An error which represents that the client sent too many requests.
+#[derive(Debug)]
+pub struct TooManyRequests {
+    limit: u32,
+    retry_after: u64,
+    reset: u64,
+}
+
+impl TooManyRequests {
+    /// Returns the number of seconds after which the client can retry the request.
+    pub fn retry_after(&self) -> u64 {
+        self.retry_after
+    }
+}
+
+impl fmt::Display for TooManyRequests {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("too many requests")
+    }
+}
+
+impl HttpError for TooManyRequests {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::TOO_MANY_REQUESTS
+    }
+
+    fn headers(&self, headers: &mut HeaderMap) {
+        headers.insert(header::RETRY_AFTER, HeaderValue::from(self.retry_after));
+        headers.insert(
+            HeaderName::from_static("ratelimit-limit"),
+            HeaderValue::from(self.limit),
+        );
+        headers.insert(
+            HeaderName::from_static("ratelimit-remaining"),
+            HeaderValue::from_static("0"),
+        );
+        headers.insert(
+            HeaderName::from_static("ratelimit-reset"),
+            HeaderValue::from(self.reset),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcra() {
+        let quota = Quota::per_second(2);
+
+        let (tat, decision) = quota.check(None, 10_000);
+        assert_eq!(tat, 10_500);
+        assert_eq!(decision, Decision::Permitted);
+
+        let (tat, decision) = quota.check(Some(tat), 10_000);
+        assert_eq!(tat, 11_000);
+        assert_eq!(decision, Decision::Permitted);
+
+        let (tat, decision) = quota.check(Some(tat), 10_100);
+        assert_eq!(tat, 11_000);
+        assert_eq!(
+            decision,
+            Decision::Rejected {
+                retry_after: 1,
+                reset: 1
+            }
+        );
+
+        // a token is refilled after 500ms.
+        let (tat, decision) = quota.check(Some(tat), 10_500);
+        assert_eq!(tat, 11_500);
+        assert_eq!(decision, Decision::Permitted);
+    }
+}
@@ -0,0 +1,48 @@
+//! An endpoint for extracting the socket address of the client.
+
+use std::net::SocketAddr;
+
+use futures_util::future::{ready, Ready};
+
+use crate::endpoint::{Context, Endpoint, EndpointResult};
+use crate::error::Error;
+
+/// Create an endpoint which extracts the socket address of the client.
+///
+/// The output is `None` if the request is not received through a connection
+/// managed by the launcher, e.g. the dummy requests without a peer address.
+///
+/// # Example
+///
+/// ```
+/// # use finchers::prelude::*;
+/// # use finchers::path;
+/// use std::net::SocketAddr;
+///
+/// let endpoint = path!(@get / "ip")
+///     .and(endpoints::remote_addr())
+///     .map(|addr: Option<SocketAddr>| match addr {
+///         Some(addr) => addr.ip().to_string(),
+///         None => "unknown".into(),
+///     });
+/// # drop(endpoint);
+/// ```
+#[inline]
+pub fn remote_addr() -> RemoteAddr {
+    (RemoteAddr { _priv: () }).with_output::<(Option<SocketAddr>,)>()
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone)]
+pub struct RemoteAddr {
+    _priv: (),
+}
+
+impl<'a> Endpoint<'a> for RemoteAddr {
+    type Output = (Option<SocketAddr>,);
+    type Future = Ready<Result<Self::Output, Error>>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        Ok(ready(Ok((cx.input().remote_addr(),))))
+    }
+}
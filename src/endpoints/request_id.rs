@@ -0,0 +1,233 @@
+//! Components for tagging each request with an identifier.
+//!
+//! The wrapper reads the identifier from the incoming request header, or
+//! generates a new one if the header is missing or invalid.
+//! The identifier is stored in the request extensions (and can be extracted
+//! with `endpoints::ext::<RequestId>()`), and is echoed in the header of the
+//! response, including the error responses. The errors also carry the identifier
+//! (see `Error::request_id()`), which is included in their text and JSON forms.
+//!
+//! # Example
+//!
+//! ```
+//! # use finchers::prelude::*;
+//! # use finchers::path;
+//! use finchers::endpoints::request_id::{request_id, RequestId};
+//!
+//! let endpoint = path!(@get / "hello")
+//!     .and(endpoints::ext::<RequestId>())
+//!     .map(|id: RequestId| format!("request id = {}", id))
+//!     .wrap(request_id());
+//! # drop(endpoint);
+//! ```
+
+use std::fmt;
+use std::pin::PinMut;
+use std::sync::Arc;
+
+use futures_core::future::{Future, TryFuture};
+use futures_core::task;
+use futures_core::task::Poll;
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use pin_utils::unsafe_pinned;
+use serde::ser::{Serialize, Serializer};
+
+use crate::common::random_token;
+use crate::endpoint::wrapper::Wrapper;
+use crate::endpoint::{Context, Endpoint, EndpointResult};
+use crate::error::Error;
+use crate::input::Input;
+
+/// The identifier associated with a request.
+#[derive(Debug, Clone)]
+pub struct RequestId {
+    id: String,
+    header_name: HeaderName,
+}
+
+impl RequestId {
+    /// Returns the string representation of this identifier.
+    pub fn as_str(&self) -> &str {
+        &self.id
+    }
+
+    pub(crate) fn set_header(&self, headers: &mut HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(&self.id) {
+            headers.insert(self.header_name.clone(), value);
+        }
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.id)
+    }
+}
+
+impl PartialEq for RequestId {
+    fn eq(&self, other: &RequestId) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Serialize for RequestId {
+    fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        ser.serialize_str(&self.id)
+    }
+}
+
+/// Create a wrapper for creating an endpoint which associates an identifier with each request.
+pub fn request_id() -> SetRequestId {
+    SetRequestId {
+        header_name: HeaderName::from_static("x-request-id"),
+        generator: Arc::new(|| random_token(16)),
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Clone)]
+pub struct SetRequestId {
+    header_name: HeaderName,
+    generator: Arc<dyn Fn() -> String + Send + Sync + 'static>,
+}
+
+impl fmt::Debug for SetRequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SetRequestId")
+            .field("header_name", &self.header_name)
+            .finish()
+    }
+}
+
+impl SetRequestId {
+    /// Sets the name of header field which carries the identifier.
+    ///
+    /// The default value is `x-request-id`.
+    pub fn header_name(self, name: HeaderName) -> SetRequestId {
+        SetRequestId {
+            header_name: name,
+            ..self
+        }
+    }
+
+    /// Sets the function which generates the identifiers.
+    ///
+    /// By default, the identifiers are 32 random hexadecimal characters.
+    pub fn generator<F>(self, f: F) -> SetRequestId
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        SetRequestId {
+            generator: Arc::new(f),
+            ..self
+        }
+    }
+
+    fn request_id(&self, input: &Input) -> RequestId {
+        let id = input
+            .headers()
+            .get(&self.header_name)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| is_valid_id(value))
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| (self.generator)());
+        RequestId {
+            id,
+            header_name: self.header_name.clone(),
+        }
+    }
+}
+
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 200 && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+impl<'a, E> Wrapper<'a, E> for SetRequestId
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Endpoint = SetRequestIdEndpoint<E>;
+
+    fn wrap(self, endpoint: E) -> Self::Endpoint {
+        SetRequestIdEndpoint {
+            endpoint,
+            config: self,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct SetRequestIdEndpoint<E> {
+    endpoint: E,
+    config: SetRequestId,
+}
+
+impl<'a, E> Endpoint<'a> for SetRequestIdEndpoint<E>
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Future = SetRequestIdFuture<E::Future>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        // The identifier is set before applying the inner endpoint, so that
+        // the error responses caused by the inner endpoint also carry it.
+        let existing = cx.input().extensions().get::<RequestId>().cloned();
+        let id = match existing {
+            Some(id) => id,
+            None => {
+                let id = self.config.request_id(&*cx.input());
+                cx.input().extensions_mut().insert(id.clone());
+                id
+            }
+        };
+        let future = self.endpoint.apply(cx)?;
+        Ok(SetRequestIdFuture { future, id })
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct SetRequestIdFuture<F> {
+    future: F,
+    id: RequestId,
+}
+
+impl<F> SetRequestIdFuture<F> {
+    unsafe_pinned!(future: F);
+}
+
+impl<F> Future for SetRequestIdFuture<F>
+where
+    F: TryFuture<Error = Error>,
+{
+    type Output = Result<F::Ok, Error>;
+
+    fn poll(mut self: PinMut<'_, Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        match self.future().try_poll(cx) {
+            Poll::Ready(Err(mut err)) => {
+                err.set_request_id(self.id.as_str());
+                Poll::Ready(Err(err))
+            }
+            polled => polled,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_id() {
+        assert!(is_valid_id("f81d4fae-7dec-11d0-a765-00a0c91e6bf6"));
+        assert!(!is_valid_id(""));
+        assert!(!is_valid_id("foo bar"));
+        assert!(!is_valid_id(&"a".repeat(201)));
+    }
+}
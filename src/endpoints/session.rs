@@ -0,0 +1,505 @@
+//! Components for managing server-side sessions.
+//!
+//! # Example
+//!
+//! ```
+//! # use finchers::prelude::*;
+//! # use finchers::path;
+//! use finchers::endpoints::session;
+//! use finchers::endpoints::session::{MemoryStore, Session};
+//! use finchers::error::Error;
+//! use std::time::Duration;
+//!
+//! let session = session::session(MemoryStore::new())
+//!     .idle_timeout(Duration::from_secs(30 * 60));
+//!
+//! let counter = path!(@get / "counter" /)
+//!     .and(session)
+//!     .map(|session: Session| -> Result<String, Error> {
+//!         let count = session.get::<u32>("count")?.unwrap_or(0) + 1;
+//!         session.set("count", count)?;
+//!         Ok(format!("count = {}", count))
+//!     });
+//! # drop(counter);
+//! ```
+//!
+//! The changes of session values are automatically persisted to the store
+//! when the response is created.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use futures_util::future::{ready, Ready};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::common::random_token;
+use crate::endpoint::{Context, Endpoint, EndpointResult};
+use crate::error::{fail, Error};
+use crate::input::cookie::{Cookie, Cookies};
+#[cfg(feature = "secure")]
+use crate::input::cookie::Key;
+
+// ==== SessionStore ====
+
+/// A record of session values stored in a `SessionStore`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    id: String,
+    data: HashMap<String, Value>,
+    created_at: i64,
+    accessed_at: i64,
+}
+
+impl SessionRecord {
+    fn new() -> SessionRecord {
+        let now = now();
+        SessionRecord {
+            id: random_token(32),
+            data: HashMap::new(),
+            created_at: now,
+            accessed_at: now,
+        }
+    }
+
+    /// Returns the identifier of this session.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Trait representing a backend which stores the session records.
+///
+/// The methods of this trait are called synchronously while the request is
+/// routed and the response is created.
+pub trait SessionStore: Send + Sync + 'static {
+    /// Loads the record associated with the current request.
+    fn load(&self, cookies: &mut Cookies<'_>) -> Result<Option<SessionRecord>, Error>;
+
+    /// Stores the record and associates it with the client.
+    fn save(&self, cookies: &mut Cookies<'_>, record: &SessionRecord) -> Result<(), Error>;
+
+    /// Removes the record with the specified identifier.
+    fn remove(&self, cookies: &mut Cookies<'_>, id: &str) -> Result<(), Error>;
+}
+
+/// A `SessionStore` which keeps the records in the process memory.
+///
+/// The client only receives the session identifier in a Cookie.
+/// The records which have not been accessed for the duration specified by
+/// `ttl` (one day by default) are treated as missing, and are periodically
+/// removed from the memory.
+#[derive(Debug)]
+pub struct MemoryStore {
+    cookie_name: String,
+    ttl: Duration,
+    inner: Mutex<MemoryStoreInner>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryStoreInner {
+    records: HashMap<String, SessionRecord>,
+    inserts: usize,
+}
+
+impl Default for MemoryStore {
+    fn default() -> MemoryStore {
+        MemoryStore::new()
+    }
+}
+
+impl MemoryStore {
+    /// Create a new `MemoryStore`.
+    pub fn new() -> MemoryStore {
+        MemoryStore {
+            cookie_name: "session-id".into(),
+            ttl: Duration::from_secs(24 * 60 * 60),
+            inner: Mutex::new(MemoryStoreInner::default()),
+        }
+    }
+
+    /// Sets the name of Cookie which stores the session identifier.
+    pub fn cookie_name(self, name: impl Into<String>) -> MemoryStore {
+        MemoryStore {
+            cookie_name: name.into(),
+            ..self
+        }
+    }
+
+    /// Sets the duration after which the records not accessed by the clients are discarded.
+    pub fn ttl(self, ttl: Duration) -> MemoryStore {
+        MemoryStore { ttl, ..self }
+    }
+
+    fn inner(&self) -> MutexGuard<'_, MemoryStoreInner> {
+        self.inner.lock().expect("the session store is poisoned")
+    }
+
+    fn is_expired(&self, record: &SessionRecord, now: i64) -> bool {
+        now - record.accessed_at > self.ttl.as_secs() as i64
+    }
+
+    fn insert(&self, record: SessionRecord) {
+        let mut inner = self.inner();
+
+        // Periodically remove the records abandoned by the clients.
+        inner.inserts += 1;
+        if inner.inserts % 1024 == 0 {
+            let now = now();
+            inner
+                .records
+                .retain(|_, record| !self.is_expired(record, now));
+        }
+
+        inner.records.insert(record.id.clone(), record);
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn load(&self, cookies: &mut Cookies<'_>) -> Result<Option<SessionRecord>, Error> {
+        Ok(cookies.get(&self.cookie_name).and_then(|cookie| {
+            self.inner()
+                .records
+                .get(cookie.value())
+                .filter(|record| !self.is_expired(record, now()))
+                .cloned()
+        }))
+    }
+
+    fn save(&self, cookies: &mut Cookies<'_>, record: &SessionRecord) -> Result<(), Error> {
+        self.insert(record.clone());
+        cookies.add(
+            Cookie::build(self.cookie_name.clone(), record.id.clone())
+                .path("/")
+                .http_only(true)
+                .finish(),
+        );
+        Ok(())
+    }
+
+    fn remove(&self, cookies: &mut Cookies<'_>, id: &str) -> Result<(), Error> {
+        self.inner().records.remove(id);
+        cookies.remove(
+            Cookie::build(self.cookie_name.clone(), "")
+                .path("/")
+                .finish(),
+        );
+        Ok(())
+    }
+}
+
+/// A `SessionStore` which stores the whole record in a signed Cookie.
+#[cfg(feature = "secure")]
+pub struct CookieStore {
+    key: Key,
+    cookie_name: String,
+}
+
+#[cfg(feature = "secure")]
+impl fmt::Debug for CookieStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CookieStore")
+            .field("cookie_name", &self.cookie_name)
+            .finish()
+    }
+}
+
+#[cfg(feature = "secure")]
+impl CookieStore {
+    /// Create a new `CookieStore` which signs the Cookie with the specified key.
+    pub fn new(key: Key) -> CookieStore {
+        CookieStore {
+            key,
+            cookie_name: "session".into(),
+        }
+    }
+
+    /// Sets the name of Cookie which stores the session record.
+    pub fn cookie_name(self, name: impl Into<String>) -> CookieStore {
+        CookieStore {
+            cookie_name: name.into(),
+            ..self
+        }
+    }
+}
+
+#[cfg(feature = "secure")]
+impl SessionStore for CookieStore {
+    fn load(&self, cookies: &mut Cookies<'_>) -> Result<Option<SessionRecord>, Error> {
+        match cookies.signed(&self.key).get(&self.cookie_name) {
+            Some(cookie) => Ok(serde_json::from_str(cookie.value()).ok()),
+            None => Ok(None),
+        }
+    }
+
+    fn save(&self, cookies: &mut Cookies<'_>, record: &SessionRecord) -> Result<(), Error> {
+        let value = serde_json::to_string(record).map_err(fail)?;
+        cookies.signed(&self.key).add(
+            Cookie::build(self.cookie_name.clone(), value)
+                .path("/")
+                .http_only(true)
+                .finish(),
+        );
+        Ok(())
+    }
+
+    fn remove(&self, cookies: &mut Cookies<'_>, _: &str) -> Result<(), Error> {
+        cookies.signed(&self.key).remove(
+            Cookie::build(self.cookie_name.clone(), "")
+                .path("/")
+                .finish(),
+        );
+        Ok(())
+    }
+}
+
+// ==== Session ====
+
+/// Create an endpoint which extracts the session associated with the current request.
+///
+/// If the client does not have a valid session, a new session is started.
+/// The session is stored only if it contains any values.
+pub fn session<S>(store: S) -> SessionEndpoint
+where
+    S: SessionStore,
+{
+    (SessionEndpoint {
+        store: Arc::new(store),
+        idle_timeout: None,
+        absolute_timeout: None,
+    }).with_output::<(Session,)>()
+}
+
+#[allow(missing_docs)]
+#[derive(Clone)]
+pub struct SessionEndpoint {
+    store: Arc<dyn SessionStore>,
+    idle_timeout: Option<Duration>,
+    absolute_timeout: Option<Duration>,
+}
+
+impl fmt::Debug for SessionEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionEndpoint")
+            .field("idle_timeout", &self.idle_timeout)
+            .field("absolute_timeout", &self.absolute_timeout)
+            .finish()
+    }
+}
+
+impl SessionEndpoint {
+    /// Sets the duration after which the session expires if the client does not access it.
+    pub fn idle_timeout(self, timeout: Duration) -> SessionEndpoint {
+        SessionEndpoint {
+            idle_timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Sets the duration after which the session expires regardless of the client activity.
+    pub fn absolute_timeout(self, timeout: Duration) -> SessionEndpoint {
+        SessionEndpoint {
+            absolute_timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    fn is_expired(&self, record: &SessionRecord, now: i64) -> bool {
+        let elapsed = |since: i64, timeout: Duration| now - since > timeout.as_secs() as i64;
+        self.idle_timeout
+            .map_or(false, |timeout| elapsed(record.accessed_at, timeout))
+            || self
+                .absolute_timeout
+                .map_or(false, |timeout| elapsed(record.created_at, timeout))
+    }
+
+    fn load(&self, cx: &mut Context<'_>) -> Result<Session, Error> {
+        if let Some(session) = cx.input().extensions().get::<Session>() {
+            return Ok(session.clone());
+        }
+
+        let mut removed_ids = vec![];
+        let loaded = {
+            let mut cookies = cx.input().cookies()?;
+            self.store.load(&mut cookies)?
+        };
+        let (record, is_new) = match loaded {
+            Some(ref record) if self.is_expired(record, now()) => {
+                removed_ids.push(record.id.clone());
+                (SessionRecord::new(), true)
+            }
+            Some(record) => (record, false),
+            None => (SessionRecord::new(), true),
+        };
+
+        let session = Session {
+            inner: Arc::new(Mutex::new(Inner {
+                record,
+                is_new,
+                destroyed: false,
+                removed_ids,
+                store: self.store.clone(),
+            })),
+        };
+        cx.input().extensions_mut().insert(session.clone());
+
+        Ok(session)
+    }
+}
+
+impl<'a> Endpoint<'a> for SessionEndpoint {
+    type Output = (Session,);
+    type Future = Ready<Result<Self::Output, Error>>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        Ok(ready(self.load(cx).map(|session| (session,))))
+    }
+}
+
+/// A handle for accessing the values of the current session.
+///
+/// The values are stored in the JSON representation.
+#[derive(Clone)]
+pub struct Session {
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Inner {
+    record: SessionRecord,
+    is_new: bool,
+    destroyed: bool,
+    removed_ids: Vec<String>,
+    store: Arc<dyn SessionStore>,
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner();
+        f.debug_struct("Session")
+            .field("record", &inner.record)
+            .field("is_new", &inner.is_new)
+            .field("destroyed", &inner.destroyed)
+            .finish()
+    }
+}
+
+impl Session {
+    fn inner(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().expect("the session is poisoned")
+    }
+
+    /// Returns the identifier of this session.
+    pub fn id(&self) -> String {
+        self.inner().record.id.clone()
+    }
+
+    /// Returns `true` if this session has been started by the current request.
+    pub fn is_new(&self) -> bool {
+        self.inner().is_new
+    }
+
+    /// Gets the value associated with the specified key.
+    pub fn get<T>(&self, key: &str) -> Result<Option<T>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        match self.inner().record.data.get(key) {
+            Some(value) => serde_json::from_value(value.clone())
+                .map(Some)
+                .map_err(fail),
+            None => Ok(None),
+        }
+    }
+
+    /// Sets the value associated with the specified key.
+    pub fn set<T>(&self, key: impl Into<String>, value: T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        let value = serde_json::to_value(value).map_err(fail)?;
+        self.inner().record.data.insert(key.into(), value);
+        Ok(())
+    }
+
+    /// Removes the value associated with the specified key.
+    pub fn remove(&self, key: &str) {
+        self.inner().record.data.remove(key);
+    }
+
+    /// Removes all values in this session.
+    pub fn clear(&self) {
+        self.inner().record.data.clear();
+    }
+
+    /// Renews the session identifier with keeping the stored values.
+    ///
+    /// This method should be called when the privilege level of the client is
+    /// changed (e.g. on login), in order to prevent session fixation attacks.
+    pub fn rotate(&self) {
+        let mut inner = self.inner();
+        let new_id = random_token(32);
+        let old_id = std::mem::replace(&mut inner.record.id, new_id);
+        if !inner.is_new {
+            inner.removed_ids.push(old_id);
+        }
+    }
+
+    /// Destroys this session.
+    pub fn destroy(&self) {
+        self.inner().destroyed = true;
+    }
+
+    /// Persists the changes to the store.
+    ///
+    /// This method is called by the runtime when the response is created.
+    pub(crate) fn persist(&self, cookies: &mut Cookies<'_>) -> Result<(), Error> {
+        let mut inner = self.inner();
+        let store = inner.store.clone();
+
+        for id in std::mem::replace(&mut inner.removed_ids, vec![]) {
+            store.remove(cookies, &id)?;
+        }
+
+        if inner.destroyed {
+            if !inner.is_new {
+                store.remove(cookies, &inner.record.id)?;
+            }
+            return Ok(());
+        }
+
+        if inner.is_new && inner.record.data.is_empty() {
+            return Ok(());
+        }
+
+        inner.record.accessed_at = now();
+        store.save(cookies, &inner.record)
+    }
+}
+
+fn now() -> i64 {
+    time::get_time().sec
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_store_purges_abandoned_records() {
+        let store = MemoryStore::new().ttl(Duration::from_secs(60));
+        for i in 0..1023 {
+            let mut record = SessionRecord::new();
+            if i % 2 == 0 {
+                record.accessed_at -= 120;
+            }
+            store.insert(record);
+        }
+        assert_eq!(store.inner().records.len(), 1023);
+
+        store.insert(SessionRecord::new());
+        assert_eq!(store.inner().records.len(), 511 + 1);
+    }
+}
@@ -0,0 +1,90 @@
+//! An endpoint for accessing the application-wide shared state.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use futures_util::future::{ready, Ready};
+use http::StatusCode;
+
+use crate::common::StateMap;
+use crate::endpoint::{Context, Endpoint, EndpointError, EndpointResult};
+use crate::error::{err_msg, Error};
+
+/// Create an endpoint which extracts a value of `T` registered by `Launcher::manage()`.
+///
+/// If the value is not registered, the endpoint fails with `500 Internal Server Error`.
+///
+/// # Example
+///
+/// ```
+/// # use finchers::prelude::*;
+/// # use finchers::path;
+/// use std::sync::Arc;
+///
+/// #[derive(Debug)]
+/// struct Config {
+///     greeting: String,
+/// }
+///
+/// let endpoint = path!(@get / "greet")
+///     .and(endpoints::state::<Config>())
+///     .map(|config: Arc<Config>| config.greeting.clone());
+///
+/// let launcher = finchers::launch(endpoint)
+///     .manage(Config {
+///         greeting: "Hello".into(),
+///     });
+/// # drop(launcher);
+/// ```
+#[inline]
+pub fn state<T>() -> State<T>
+where
+    T: Send + Sync + 'static,
+{
+    (State {
+        _marker: PhantomData,
+    }).with_output::<(Arc<T>,)>()
+}
+
+#[allow(missing_docs)]
+pub struct State<T> {
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> fmt::Debug for State<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("State").finish()
+    }
+}
+
+impl<T> Copy for State<T> {}
+
+impl<T> Clone for State<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Endpoint<'a> for State<T>
+where
+    T: Send + Sync + 'static,
+{
+    type Output = (Arc<T>,);
+    type Future = Ready<Result<Self::Output, Error>>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let value = cx
+            .input()
+            .extensions()
+            .get::<Arc<StateMap>>()
+            .and_then(|state| state.get::<T>())
+            .ok_or_else(|| {
+                EndpointError::custom(err_msg(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "the requested state is not registered",
+                ))
+            })?;
+        Ok(ready(Ok((value,))))
+    }
+}
@@ -0,0 +1,149 @@
+//! A wrapper for limiting the time to resolve the future of an endpoint.
+
+use std::fmt;
+use std::pin::PinMut;
+use std::time::{Duration, Instant};
+
+use futures::Future as Future01;
+use futures_core::future::{Future, TryFuture};
+use futures_core::task;
+use futures_core::task::Poll;
+use http::StatusCode;
+use pin_utils::{unsafe_pinned, unsafe_unpinned};
+use tokio::timer::Delay;
+
+use crate::common::poll_01_with_cx;
+use crate::endpoint::wrapper::Wrapper;
+use crate::endpoint::{Context, Endpoint, EndpointResult};
+use crate::error::{fail, Error, HttpError};
+
+/// Creates a wrapper for creating an endpoint which fails with an error
+/// if the future returned from the inner endpoint does not complete within
+/// the specified duration.
+///
+/// The status code of the error is `503 Service Unavailable` by default.
+pub fn timeout(duration: Duration) -> Timeout {
+    Timeout {
+        duration,
+        status: StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone)]
+pub struct Timeout {
+    duration: Duration,
+    status: StatusCode,
+}
+
+impl Timeout {
+    /// Sets the status code of the error returned when the timeout elapses.
+    ///
+    /// The typical values are `503 Service Unavailable` and `504 Gateway Timeout`.
+    pub fn status(self, status: StatusCode) -> Timeout {
+        Timeout { status, ..self }
+    }
+}
+
+impl<'a, E> Wrapper<'a, E> for Timeout
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Endpoint = TimeoutEndpoint<E>;
+
+    fn wrap(self, endpoint: E) -> Self::Endpoint {
+        TimeoutEndpoint {
+            endpoint,
+            timeout: self,
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone)]
+pub struct TimeoutEndpoint<E> {
+    endpoint: E,
+    timeout: Timeout,
+}
+
+impl<'a, E> Endpoint<'a> for TimeoutEndpoint<E>
+where
+    E: Endpoint<'a>,
+{
+    type Output = E::Output;
+    type Future = TimeoutFuture<E::Future>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let future = self.endpoint.apply(cx)?;
+        Ok(TimeoutFuture {
+            future,
+            delay: Delay::new(Instant::now() + self.timeout.duration),
+            timeout: self.timeout,
+        })
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Debug)]
+pub struct TimeoutFuture<F> {
+    future: F,
+    delay: Delay,
+    timeout: Timeout,
+}
+
+impl<F> TimeoutFuture<F> {
+    unsafe_pinned!(future: F);
+    unsafe_unpinned!(delay: Delay);
+}
+
+impl<F> Future for TimeoutFuture<F>
+where
+    F: TryFuture<Error = Error>,
+{
+    type Output = Result<F::Ok, Error>;
+
+    fn poll(mut self: PinMut<'_, Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(result) = self.future().try_poll(cx) {
+            return Poll::Ready(result);
+        }
+
+        let Timeout { duration, status } = self.timeout;
+        let delay = self.delay();
+        match poll_01_with_cx(cx, || delay.poll()) {
+            Poll::Ready(Ok(())) => Poll::Ready(Err(TimedOut::new(status, duration).into())),
+            Poll::Ready(Err(err)) => Poll::Ready(Err(fail(err))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// An error which represents that an operation did not complete within the time limit.
+#[derive(Debug)]
+pub struct TimedOut {
+    status: StatusCode,
+    duration: Duration,
+}
+
+impl TimedOut {
+    pub(crate) fn new(status: StatusCode, duration: Duration) -> TimedOut {
+        TimedOut { status, duration }
+    }
+
+    /// Returns the time limit which has elapsed.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the operation timed out after {:?}", self.duration)
+    }
+}
+
+impl HttpError for TimedOut {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+}
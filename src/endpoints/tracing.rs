@@ -0,0 +1,520 @@
+//! Components for the distributed tracing based on [W3C Trace Context].
+//!
+//! The wrapper created by `tracing()` continues the trace given by the `traceparent` and
+//! `tracestate` headers of the request (or starts a new trace if they are missing or invalid),
+//! and records a span which covers the time between applying the inner endpoint and resolving
+//! its future. The finished spans are passed to an `Exporter`.
+//!
+//! The trace context of the current span is stored in the request extensions, and
+//! can be extracted with `endpoints::ext::<TraceContext>()` in order to propagate it
+//! to the outgoing requests.
+//!
+//! [W3C Trace Context]: https://www.w3.org/TR/trace-context/
+//!
+//! # Example
+//!
+//! ```
+//! # use finchers::prelude::*;
+//! # use finchers::path;
+//! use finchers::endpoints::tracing::{tracing, LogExporter, TraceContext};
+//!
+//! let endpoint = path!(@get / "hello")
+//!     .and(endpoints::ext::<TraceContext>())
+//!     .map(|cx: TraceContext| {
+//!         // The value of `traceparent` header for the outgoing requests.
+//!         cx.to_traceparent()
+//!     })
+//!     .wrap(tracing(LogExporter));
+//! # drop(endpoint);
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::pin::PinMut;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use futures_core::future::{Future, TryFuture};
+use futures_core::task;
+use futures_core::task::Poll;
+use http::{Response, StatusCode};
+use log::info;
+use pin_utils::{unsafe_pinned, unsafe_unpinned};
+
+use crate::common::random_token;
+use crate::endpoint::wrapper::Wrapper;
+use crate::endpoint::{Context, Endpoint, EndpointResult};
+use crate::error::{Error, Never};
+use crate::input::{with_get_cx, Input};
+use crate::output::payload::Payload;
+use crate::output::{Output, OutputContext};
+
+// ==== TraceContext ====
+
+/// The trace context associated with the span of the current request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceContext {
+    trace_id: String,
+    span_id: String,
+    parent_span_id: Option<String>,
+    sampled: bool,
+    tracestate: Option<String>,
+}
+
+impl TraceContext {
+    fn from_input(input: &Input) -> TraceContext {
+        let parent = input
+            .headers()
+            .get("traceparent")
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_traceparent);
+
+        match parent {
+            Some((trace_id, parent_span_id, sampled)) => {
+                let tracestate = input
+                    .headers()
+                    .get_all("tracestate")
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                TraceContext {
+                    trace_id,
+                    span_id: generate_id(8),
+                    parent_span_id: Some(parent_span_id),
+                    sampled,
+                    tracestate: if tracestate.is_empty() {
+                        None
+                    } else {
+                        Some(tracestate)
+                    },
+                }
+            }
+            None => TraceContext {
+                trace_id: generate_id(16),
+                span_id: generate_id(8),
+                parent_span_id: None,
+                sampled: true,
+                tracestate: None,
+            },
+        }
+    }
+
+    /// Returns the trace ID, encoded as 32 lowercase hexadecimal characters.
+    pub fn trace_id(&self) -> &str {
+        &self.trace_id
+    }
+
+    /// Returns the ID of the current span, encoded as 16 lowercase hexadecimal characters.
+    pub fn span_id(&self) -> &str {
+        &self.span_id
+    }
+
+    /// Returns the ID of the parent span, if the trace was continued from the request.
+    pub fn parent_span_id(&self) -> Option<&str> {
+        self.parent_span_id.as_ref().map(|s| s.as_str())
+    }
+
+    /// Returns whether the caller has recorded this trace.
+    pub fn is_sampled(&self) -> bool {
+        self.sampled
+    }
+
+    /// Returns the vendor-specific trace information received with the request.
+    pub fn tracestate(&self) -> Option<&str> {
+        self.tracestate.as_ref().map(|s| s.as_str())
+    }
+
+    /// Returns the value of `traceparent` header to be sent with the outgoing requests.
+    pub fn to_traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{}",
+            self.trace_id,
+            self.span_id,
+            if self.sampled { "01" } else { "00" }
+        )
+    }
+}
+
+fn generate_id(len: usize) -> String {
+    loop {
+        let id = random_token(len);
+        if !is_zero(&id) {
+            return id;
+        }
+    }
+}
+
+fn is_zero(id: &str) -> bool {
+    id.bytes().all(|b| b == b'0')
+}
+
+fn is_valid_id(id: &str, len: usize) -> bool {
+    id.len() == len
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a' <= b && b <= b'f'))
+        && !is_zero(id)
+}
+
+/// Parses the value of `traceparent` header and returns the trace ID, the parent ID and the
+/// sampled flag.
+fn parse_traceparent(s: &str) -> Option<(String, String, bool)> {
+    let mut parts = s.trim().split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let parent_id = parts.next()?;
+    let flags = parts.next()?;
+
+    if version.len() != 2 || !version.bytes().all(|b| b.is_ascii_hexdigit()) || version == "ff" {
+        return None;
+    }
+    // The version 00 does not allow any additional fields.
+    if version == "00" && parts.next().is_some() {
+        return None;
+    }
+    if !is_valid_id(trace_id, 32) || !is_valid_id(parent_id, 16) || flags.len() != 2 {
+        return None;
+    }
+    let flags = u8::from_str_radix(flags, 16).ok()?;
+
+    Some((trace_id.to_owned(), parent_id.to_owned(), flags & 0x01 != 0))
+}
+
+// ==== Span / Exporter ====
+
+/// A finished span passed to the exporter.
+#[allow(missing_docs)]
+#[derive(Debug, Clone)]
+pub struct Span {
+    pub context: TraceContext,
+    pub name: String,
+    pub start: SystemTime,
+    pub duration: Duration,
+    /// The status code of the response, or `None` if the request was cancelled
+    /// before completion.
+    pub status: Option<StatusCode>,
+    _priv: (),
+}
+
+/// A trait representing the destination of the finished spans.
+pub trait Exporter: Send + Sync + 'static {
+    /// Exports a finished span.
+    fn export(&self, span: Span);
+}
+
+impl<F> Exporter for F
+where
+    F: Fn(Span) + Send + Sync + 'static,
+{
+    fn export(&self, span: Span) {
+        (*self)(span)
+    }
+}
+
+/// An exporter which dumps the finished spans to the logger.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct LogExporter;
+
+impl Exporter for LogExporter {
+    fn export(&self, span: Span) {
+        info!(
+            "span {} (trace_id={}, span_id={}, parent_id={}, status={}, duration={:?})",
+            span.name,
+            span.context.trace_id(),
+            span.context.span_id(),
+            span.context.parent_span_id().unwrap_or("-"),
+            span.status
+                .map_or_else(|| "-".to_owned(), |s| s.as_u16().to_string()),
+            span.duration,
+        );
+    }
+}
+
+/// An exporter which stores the finished spans in memory.
+///
+/// The values of this type are cheaply cloneable and share the same storage.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryExporter {
+    spans: Arc<Mutex<Vec<Span>>>,
+}
+
+impl InMemoryExporter {
+    /// Creates an empty exporter.
+    pub fn new() -> InMemoryExporter {
+        InMemoryExporter::default()
+    }
+
+    /// Returns the spans exported so far.
+    pub fn spans(&self) -> Vec<Span> {
+        self.spans
+            .lock()
+            .expect("the span storage is poisoned")
+            .clone()
+    }
+}
+
+impl Exporter for InMemoryExporter {
+    fn export(&self, span: Span) {
+        self.spans
+            .lock()
+            .expect("the span storage is poisoned")
+            .push(span);
+    }
+}
+
+// ==== Tracing ====
+
+/// Create a wrapper for creating an endpoint which records a span for each request.
+pub fn tracing<X>(exporter: X) -> Tracing<X>
+where
+    X: Exporter,
+{
+    Tracing {
+        exporter,
+        name: None,
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Debug)]
+pub struct Tracing<X> {
+    exporter: X,
+    name: Option<Cow<'static, str>>,
+}
+
+impl<X> Tracing<X> {
+    /// Sets the name of spans.
+    ///
+    /// By default, the name is built from the method and the path of the request.
+    pub fn name(self, name: impl Into<Cow<'static, str>>) -> Tracing<X> {
+        Tracing {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+}
+
+impl<'a, E, X> Wrapper<'a, E> for Tracing<X>
+where
+    E: Endpoint<'a>,
+    E::Output: Output,
+    X: Exporter,
+{
+    type Output = (TracedResponse<<E::Output as Output>::Body>,);
+    type Endpoint = TracingEndpoint<E, X>;
+
+    fn wrap(self, endpoint: E) -> Self::Endpoint {
+        TracingEndpoint {
+            endpoint,
+            tracing: self,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct TracingEndpoint<E, X> {
+    endpoint: E,
+    tracing: Tracing<X>,
+}
+
+impl<'a, E, X> Endpoint<'a> for TracingEndpoint<E, X>
+where
+    E: Endpoint<'a>,
+    E::Output: Output,
+    X: Exporter,
+{
+    type Output = (TracedResponse<<E::Output as Output>::Body>,);
+    type Future = TracingFuture<'a, E::Future>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let start = SystemTime::now();
+        let instant = Instant::now();
+
+        // The context is set before applying the inner endpoint so that
+        // it can be extracted by the inner endpoints.
+        let context = TraceContext::from_input(&*cx.input());
+        let name = match self.tracing.name {
+            Some(ref name) => name.to_string(),
+            None => format!("{} {}", cx.input().method(), cx.input().uri().path()),
+        };
+        let previous = cx.input().extensions_mut().insert(context.clone());
+
+        let future = match self.endpoint.apply(cx) {
+            Ok(future) => future,
+            Err(err) => {
+                // Restore the extensions so that the context is not visible from
+                // the other routes tried after this endpoint.
+                let extensions = cx.input().extensions_mut();
+                match previous {
+                    Some(previous) => {
+                        extensions.insert(previous);
+                    }
+                    None => {
+                        extensions.remove::<TraceContext>();
+                    }
+                }
+                return Err(err);
+            }
+        };
+        Ok(TracingFuture {
+            future,
+            span: Some(SpanGuard {
+                exporter: &self.tracing.exporter,
+                context,
+                name,
+                start,
+                instant,
+                finished: false,
+            }),
+        })
+    }
+}
+
+/// A guard which exports the span even if the future is dropped before completion.
+struct SpanGuard<'a> {
+    exporter: &'a dyn Exporter,
+    context: TraceContext,
+    name: String,
+    start: SystemTime,
+    instant: Instant,
+    finished: bool,
+}
+
+impl<'a> fmt::Debug for SpanGuard<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpanGuard")
+            .field("context", &self.context)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl<'a> SpanGuard<'a> {
+    fn export(&mut self, status: Option<StatusCode>) {
+        self.finished = true;
+        self.exporter.export(Span {
+            context: self.context.clone(),
+            name: self.name.clone(),
+            start: self.start,
+            duration: self.instant.elapsed(),
+            status,
+            _priv: (),
+        });
+    }
+}
+
+impl<'a> Drop for SpanGuard<'a> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.export(None);
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct TracingFuture<'a, Fut> {
+    future: Fut,
+    span: Option<SpanGuard<'a>>,
+}
+
+impl<'a, Fut> TracingFuture<'a, Fut> {
+    unsafe_pinned!(future: Fut);
+    unsafe_unpinned!(span: Option<SpanGuard<'a>>);
+}
+
+impl<'a, Fut> Future for TracingFuture<'a, Fut>
+where
+    Fut: TryFuture<Error = Error>,
+    Fut::Ok: Output,
+{
+    type Output = Result<(TracedResponse<<Fut::Ok as Output>::Body>,), Error>;
+
+    fn poll(mut self: PinMut<'_, Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let result = match self.future().try_poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(result) => result.and_then(|x| {
+                with_get_cx(|input| {
+                    let mut ocx = OutputContext::new(input);
+                    x.respond(&mut ocx).map_err(Into::into)
+                })
+            }),
+        };
+
+        let mut span = self.span().take().expect("the future has already polled");
+        match result {
+            Ok(response) => {
+                span.export(Some(response.status()));
+                Poll::Ready(Ok((TracedResponse(response),)))
+            }
+            Err(err) => {
+                span.export(Some(err.status_code()));
+                Poll::Ready(Err(err))
+            }
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct TracedResponse<Bd>(Response<Bd>);
+
+impl<Bd: Payload> Output for TracedResponse<Bd> {
+    type Body = Bd;
+    type Error = Never;
+
+    #[inline(always)]
+    fn respond(self, _: &mut OutputContext<'_>) -> Result<Response<Self::Body>, Self::Error> {
+        Ok(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_traceparent() {
+        assert_eq!(
+            parse_traceparent("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"),
+            Some((
+                "0af7651916cd43dd8448eb211c80319c".to_owned(),
+                "b7ad6b7169203331".to_owned(),
+                true
+            ))
+        );
+        assert_eq!(
+            parse_traceparent("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00")
+                .map(|t| t.2),
+            Some(false)
+        );
+        // future versions may have additional fields.
+        assert!(
+            parse_traceparent("01-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01-foo")
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_parse_traceparent_invalid() {
+        assert!(parse_traceparent("").is_none());
+        assert!(
+            parse_traceparent("ff-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01").is_none()
+        );
+        assert!(
+            parse_traceparent("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01-foo")
+                .is_none()
+        );
+        assert!(
+            parse_traceparent("00-00000000000000000000000000000000-b7ad6b7169203331-01").is_none()
+        );
+        assert!(
+            parse_traceparent("00-0af7651916cd43dd8448eb211c80319c-0000000000000000-01").is_none()
+        );
+        assert!(
+            parse_traceparent("00-0AF7651916CD43DD8448EB211C80319C-b7ad6b7169203331-01").is_none()
+        );
+    }
+}
@@ -58,36 +58,42 @@ impl HttpError for failure::Error {
 
 /// A type which holds a value of `HttpError` in a type-erased form.
 #[derive(Debug)]
-pub struct Error(Box<dyn HttpError>);
+pub struct Error {
+    inner: Box<dyn HttpError>,
+    request_id: Option<String>,
+}
 
 impl<E: HttpError> From<E> for Error {
     fn from(err: E) -> Self {
-        Error(Box::new(err))
+        Error {
+            inner: Box::new(err),
+            request_id: None,
+        }
     }
 }
 
 impl AsRef<dyn HttpError> for Error {
     fn as_ref(&self) -> &dyn HttpError {
-        &*self.0
+        &*self.inner
     }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        Display::fmt(&*self.0, f)
+        Display::fmt(&*self.inner, f)
     }
 }
 
 impl Error {
     /// Returns `true` if the type of contained value is the same as `T`.
     pub fn is<T: HttpError>(&self) -> bool {
-        self.0.__private_type_id__() == TypeId::of::<T>()
+        self.inner.__private_type_id__() == TypeId::of::<T>()
     }
 
     /// Attempts to downcast the boxed value to a conrete type by reference.
     pub fn downcast_ref<T: HttpError>(&self) -> Option<&T> {
         if self.is::<T>() {
-            unsafe { Some(&*(&*self.0 as *const dyn HttpError as *const T)) }
+            unsafe { Some(&*(&*self.inner as *const dyn HttpError as *const T)) }
         } else {
             None
         }
@@ -96,7 +102,7 @@ impl Error {
     /// Attempts to downcast the boxed value to a conrete type by mutable reference.
     pub fn downcast_mut<T: HttpError>(&mut self) -> Option<&mut T> {
         if self.is::<T>() {
-            unsafe { Some(&mut *(&mut *self.0 as *mut dyn HttpError as *mut T)) }
+            unsafe { Some(&mut *(&mut *self.inner as *mut dyn HttpError as *mut T)) }
         } else {
             None
         }
@@ -107,7 +113,7 @@ impl Error {
         if self.is::<T>() {
             unsafe {
                 Ok(*Box::from_raw(
-                    Box::into_raw(self.0) as *mut dyn HttpError as *mut T
+                    Box::into_raw(self.inner) as *mut dyn HttpError as *mut T
                 ))
             }
         } else {
@@ -117,27 +123,43 @@ impl Error {
 
     /// Return the HTTP status code associated with contained value.
     pub fn status_code(&self) -> StatusCode {
-        self.0.status_code()
+        self.inner.status_code()
     }
 
     /// Append a set of header values to the header map.
     pub fn headers(&self, headers: &mut HeaderMap) {
-        self.0.headers(headers)
+        self.inner.headers(headers)
     }
 
     /// Returns a reference to the underlying cause of contained error value.
     pub fn cause(&self) -> Option<&dyn Fail> {
-        self.0.cause()
+        self.inner.cause()
+    }
+
+    /// Returns the identifier of the request which caused this error, if available.
+    ///
+    /// The value is set when the error passes through the wrapper created by
+    /// `endpoints::request_id::request_id()`.
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_ref().map(|id| id.as_str())
+    }
+
+    pub(crate) fn set_request_id(&mut self, request_id: &str) {
+        self.request_id = Some(request_id.to_owned());
     }
 
     pub(crate) fn to_response(&self) -> Response<String> {
-        let mut response = Response::new(format!("{:#}", self.0));
+        let mut body = format!("{:#}", self.inner);
+        if let Some(ref request_id) = self.request_id {
+            body += &format!("\n(request id: {})", request_id);
+        }
+        let mut response = Response::new(body);
         *response.status_mut() = self.status_code();
         response.headers_mut().insert(
             header::CONTENT_TYPE,
             HeaderValue::from_static("text/plain; charset=utf-8"),
         );
-        self.0.headers(response.headers_mut());
+        self.inner.headers(response.headers_mut());
         response
     }
 }
@@ -150,6 +172,9 @@ impl Serialize for Error {
         let mut map = ser.serialize_map(None)?;
         map.serialize_entry("code", &self.status_code().as_u16())?;
         map.serialize_entry("description", &self.to_string())?;
+        if let Some(ref request_id) = self.request_id {
+            map.serialize_entry("request_id", request_id)?;
+        }
         // TODO: causes
         map.end()
     }
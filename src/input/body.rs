@@ -3,37 +3,49 @@
 use std::pin::PinMut;
 use std::string::FromUtf8Error;
 use std::task::{self, Poll};
+use std::time::{Duration, Instant};
 
 use bytes::Bytes;
 use failure::Fail;
-use futures::{self as futures01, Async};
+use futures::Future as Future01;
 use http::header::HeaderMap;
+use http::StatusCode;
 use hyper::body::{Body, Chunk, Payload as _Payload};
-use pin_utils::unsafe_unpinned;
+use tokio::timer::Delay;
 
+use crate::common::poll_01_with_cx;
+use crate::endpoints::timeout::TimedOut;
 use crate::error::{fail, Error, Never};
 use crate::input::Input;
 
 #[derive(Debug)]
 pub struct Payload {
     body: Body,
+    timeout: Option<Duration>,
+    delay: Option<Delay>,
 }
 
 impl Payload {
-    unsafe_unpinned!(body: Body);
-
     pub fn poll_data(
-        mut self: PinMut<'_, Self>,
+        self: PinMut<'_, Self>,
         cx: &mut task::Context<'_>,
     ) -> Poll<Result<Option<Chunk>, Error>> {
-        poll_01_with_cx(cx, || self.body().poll_data()).map_err(fail)
+        let this = unsafe { PinMut::get_mut_unchecked(self) };
+        match poll_01_with_cx(cx, || this.body.poll_data()) {
+            Poll::Ready(result) => Poll::Ready(result.map_err(fail)),
+            Poll::Pending => this.poll_timeout(cx),
+        }
     }
 
     pub fn poll_trailers(
-        mut self: PinMut<'_, Self>,
+        self: PinMut<'_, Self>,
         cx: &mut task::Context<'_>,
     ) -> Poll<Result<Option<HeaderMap>, Error>> {
-        poll_01_with_cx(cx, || self.body().poll_trailers()).map_err(fail)
+        let this = unsafe { PinMut::get_mut_unchecked(self) };
+        match poll_01_with_cx(cx, || this.body.poll_trailers()) {
+            Poll::Ready(result) => Poll::Ready(result.map_err(fail)),
+            Poll::Pending => this.poll_timeout(cx),
+        }
     }
 
     pub fn is_end_stream(&self) -> bool {
@@ -43,25 +55,68 @@ impl Payload {
     pub fn content_length(&self) -> Option<u64> {
         self.body.content_length()
     }
+
+    /// Returns an error if the time limit for reading the body has elapsed.
+    ///
+    /// The timer starts at the first time when the body is not ready.
+    fn poll_timeout<T>(&mut self, cx: &mut task::Context<'_>) -> Poll<Result<T, Error>> {
+        let timeout = match self.timeout {
+            Some(timeout) => timeout,
+            None => return Poll::Pending,
+        };
+        let delay = self
+            .delay
+            .get_or_insert_with(|| Delay::new(Instant::now() + timeout));
+        match poll_01_with_cx(cx, || delay.poll()) {
+            Poll::Ready(Ok(())) => {
+                let err = TimedOut::new(StatusCode::REQUEST_TIMEOUT, timeout);
+                Poll::Ready(Err(err.into()))
+            }
+            Poll::Ready(Err(err)) => Poll::Ready(Err(fail(err))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
 }
 
 /// An asyncrhonous stream to receive the chunks of incoming request body.
 #[derive(Debug)]
-pub struct ReqBody(Option<Body>);
+pub struct ReqBody {
+    body: Option<Body>,
+    timeout: Option<Duration>,
+}
 
 impl ReqBody {
     /// Create an instance of `RequestBody` from `hyper::Body`.
     pub fn from_hyp(body: Body) -> ReqBody {
-        ReqBody(Some(body))
+        ReqBody {
+            body: Some(body),
+            timeout: None,
+        }
+    }
+
+    /// Sets the time limit for reading the whole of message body.
+    pub(crate) fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+    }
+
+    /// Replaces the message body, with keeping the time limit.
+    #[cfg_attr(not(feature = "secure"), allow(dead_code))]
+    pub(crate) fn replace(&mut self, body: Body) {
+        self.body = Some(body);
     }
 
     #[allow(missing_docs)]
     pub fn payload(&mut self) -> Option<Payload> {
-        self.0.take().map(|body| Payload { body })
+        let timeout = self.timeout;
+        self.body.take().map(|body| Payload {
+            body,
+            timeout,
+            delay: None,
+        })
     }
 
     pub fn is_gone(&self) -> bool {
-        self.0.is_none()
+        self.body.is_none()
     }
 }
 
@@ -90,52 +145,3 @@ impl FromBody for String {
         String::from_utf8(body.to_vec())
     }
 }
-
-// ==== compat ====
-
-fn poll_01_with_cx<T, E>(
-    cx: &mut task::Context<'_>,
-    f: impl FnOnce() -> futures01::Poll<T, E>,
-) -> Poll<Result<T, E>> {
-    // FIXME: Set the executor to the global context for futures-0.1
-    let notify = &WakerToHandle(cx.waker());
-
-    futures01::executor::with_notify(notify, 0, move || match f() {
-        Ok(Async::Ready(ok)) => Poll::Ready(Ok(ok)),
-        Ok(Async::NotReady) => Poll::Pending,
-        Err(err) => Poll::Ready(Err(err)),
-    })
-}
-
-#[allow(missing_debug_implementations)]
-struct NotifyWaker(task::Waker);
-
-#[derive(Clone)]
-#[allow(missing_debug_implementations)]
-struct WakerToHandle<'a>(&'a task::Waker);
-
-impl<'a> From<WakerToHandle<'a>> for futures01::executor::NotifyHandle {
-    fn from(handle: WakerToHandle<'a>) -> futures01::executor::NotifyHandle {
-        let ptr = Box::new(NotifyWaker(handle.0.clone()));
-        unsafe { futures01::executor::NotifyHandle::new(Box::into_raw(ptr)) }
-    }
-}
-
-impl futures01::executor::Notify for NotifyWaker {
-    fn notify(&self, _: usize) {
-        self.0.wake()
-    }
-}
-
-unsafe impl futures01::executor::UnsafeNotify for NotifyWaker {
-    unsafe fn clone_raw(&self) -> futures01::executor::NotifyHandle {
-        WakerToHandle(&self.0).into()
-    }
-
-    unsafe fn drop_raw(&self) {
-        let ptr: *const dyn futures01::executor::UnsafeNotify = self;
-        drop(Box::from_raw(
-            ptr as *mut dyn futures01::executor::UnsafeNotify,
-        ));
-    }
-}
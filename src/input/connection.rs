@@ -0,0 +1,46 @@
+use std::net::SocketAddr;
+
+/// The information of the connection on which an HTTP request is received.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    remote_addr: Option<SocketAddr>,
+    local_addr: Option<SocketAddr>,
+    secure: bool,
+}
+
+impl ConnectionInfo {
+    pub(crate) fn new(
+        remote_addr: Option<SocketAddr>,
+        local_addr: Option<SocketAddr>,
+        secure: bool,
+    ) -> ConnectionInfo {
+        ConnectionInfo {
+            remote_addr,
+            local_addr,
+            secure,
+        }
+    }
+
+    /// Returns the socket address of the peer, if available.
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+
+    /// Returns the socket address of the local end of the connection, if available.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Returns whether the connection is secured with TLS.
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+}
+
+/// The client-facing values of the request determined from the headers added by trusted proxies.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Forwarded {
+    pub(crate) remote_addr: Option<SocketAddr>,
+    pub(crate) scheme: Option<String>,
+    pub(crate) host: Option<String>,
+}
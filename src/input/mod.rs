@@ -5,9 +5,12 @@ pub mod cookie;
 pub mod header;
 pub mod query;
 
+mod connection;
 mod encoded;
 mod global;
 
+pub use self::connection::ConnectionInfo;
+pub(crate) use self::connection::Forwarded;
 pub use self::encoded::{EncodedStr, FromEncodedStr};
 
 pub use self::global::with_get_cx;
@@ -17,10 +20,12 @@ pub(crate) use self::global::with_set_cx;
 
 use cookie::CookieJar;
 use http;
-use http::Request;
+use http::{Request, Uri};
+use hyper::body::Body;
 use mime::Mime;
 use std::cell::UnsafeCell;
 use std::marker::{PhantomData, Pinned};
+use std::net::SocketAddr;
 use std::ops::Deref;
 use std::pin::PinMut;
 
@@ -54,6 +59,65 @@ impl Input {
         &self.request
     }
 
+    /// Returns the information of the connection on which this request is received.
+    ///
+    /// The value is `None` if the request is not received through a connection
+    /// managed by the launcher.
+    pub fn connection(&self) -> Option<&ConnectionInfo> {
+        self.request.extensions().get::<ConnectionInfo>()
+    }
+
+    fn forwarded(&self) -> Option<&Forwarded> {
+        self.request.extensions().get::<Forwarded>()
+    }
+
+    /// Returns the socket address of the client, if available.
+    ///
+    /// If the request is forwarded by trusted proxies, the returned value is
+    /// the address of the original client rather than the peer of the connection.
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.forwarded()
+            .and_then(|forwarded| forwarded.remote_addr)
+            .or_else(|| self.connection().and_then(|conn| conn.remote_addr()))
+    }
+
+    /// Returns the socket address of the local end of the connection, if available.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.connection().and_then(|conn| conn.local_addr())
+    }
+
+    /// Returns the URI scheme used by the client, either `"http"` or `"https"`.
+    ///
+    /// If the request is forwarded by trusted proxies, the returned value is
+    /// the scheme of the original request.
+    pub fn scheme(&self) -> &str {
+        if let Some(scheme) = self.forwarded().and_then(|f| f.scheme.as_ref()) {
+            return scheme;
+        }
+        if let Some(scheme) = self.request.uri().scheme_part() {
+            return scheme.as_str();
+        }
+        match self.connection() {
+            Some(conn) if conn.is_secure() => "https",
+            _ => "http",
+        }
+    }
+
+    /// Returns the host (and the port, if specified) which the client sends the request to.
+    ///
+    /// If the request is forwarded by trusted proxies, the returned value is
+    /// the host of the original request.
+    pub fn host(&self) -> Option<&str> {
+        if let Some(host) = self.forwarded().and_then(|f| f.host.as_ref()) {
+            return Some(host);
+        }
+        self.request
+            .headers()
+            .get(http::header::HOST)
+            .and_then(|host| host.to_str().ok())
+            .or_else(|| self.request.uri().authority_part().map(|a| a.as_str()))
+    }
+
     /// Takes the instance of `RequestBody` from this value.
     #[inline]
     pub fn payload(self: PinMut<'_, Self>) -> Option<Payload> {
@@ -107,9 +171,42 @@ impl Input {
         }
     }
 
+    /// Returns a shared reference to the typed extensions associated with this request.
+    ///
+    /// The extensions can be used for sharing the per-request values
+    /// between endpoints, e.g. the authenticated principal or request ID.
+    pub fn extensions(&self) -> &http::Extensions {
+        self.request.extensions()
+    }
+
+    /// Returns a mutable reference to the typed extensions associated with this request.
+    #[allow(clippy::needless_lifetimes)]
+    pub fn extensions_mut<'a>(self: PinMut<'a, Self>) -> &'a mut http::Extensions {
+        let this = unsafe { PinMut::get_mut_unchecked(self) };
+        this.request.extensions_mut()
+    }
+
     pub(crate) fn cookie_jar(&self) -> Option<&CookieJar> {
         self.cookie_jar.as_ref()
     }
+
+    /// Replaces the message body with the provided one.
+    ///
+    /// This is used by the components which need to inspect the message body
+    /// before it is passed to the subsequent endpoints.
+    #[cfg_attr(not(feature = "secure"), allow(dead_code))]
+    pub(crate) fn replace_body(self: PinMut<'_, Self>, body: Body) {
+        let this = unsafe { PinMut::get_mut_unchecked(self) };
+        this.request.body_mut().replace(body);
+    }
+
+    /// Replaces the request URI with the provided one.
+    ///
+    /// This is used by the components which rewrite the path before routing.
+    pub(crate) fn set_uri(self: PinMut<'_, Self>, uri: Uri) {
+        let this = unsafe { PinMut::get_mut_unchecked(self) };
+        *this.request.uri_mut() = uri;
+    }
 }
 
 impl Deref for Input {
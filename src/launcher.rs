@@ -1,16 +1,28 @@
 //! Components for managing HTTP server.
 
 use failure::{err_msg, Fallible};
-use futures::Future;
+use futures::{try_ready, Async, Future, Poll, Stream};
+use http::header::HeaderMap;
+use http::Response;
+use hyper::body::{Body, Payload};
 use hyper::server::conn::Http;
-use hyper::server::Builder;
+use hyper::service::Service;
+use hyper::Request;
 use log::error;
+use std::io;
 use std::net::ToSocketAddrs;
-use tokio::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::tcp::Incoming;
+use tokio::net::{TcpListener, TcpStream};
 use tokio::runtime::Runtime;
+use tokio::timer::Delay;
 
-use crate::app::App;
+use crate::app::{App, Timeouts};
+use crate::common::StateMap;
 use crate::endpoint::Endpoint;
+use crate::input::ConnectionInfo;
 use crate::output::Output;
 
 // ==== LaunchEndpoint ====
@@ -85,6 +97,12 @@ where
     endpoint: E,
     http: Option<Http>,
     rt: Option<Runtime>,
+    state: StateMap,
+    header_read_timeout: Option<Duration>,
+    timeouts: Timeouts,
+    tcp_nodelay: bool,
+    tcp_keepalive: Option<Duration>,
+    secure: bool,
 }
 
 impl<E> Launcher<E>
@@ -107,6 +125,79 @@ where
         }
     }
 
+    /// Sets whether to set the `TCP_NODELAY` option on the accepted connections.
+    ///
+    /// The default value is `true`.
+    pub fn tcp_nodelay(self, enabled: bool) -> Self {
+        Launcher {
+            tcp_nodelay: enabled,
+            ..self
+        }
+    }
+
+    /// Sets the interval of TCP keepalive probes on the accepted connections.
+    ///
+    /// By default, the TCP keepalive is disabled.
+    pub fn tcp_keepalive(self, interval: Duration) -> Self {
+        Launcher {
+            tcp_keepalive: Some(interval),
+            ..self
+        }
+    }
+
+    /// Sets whether the connections accepted by the listener are secured with TLS.
+    ///
+    /// The listener does not terminate TLS by itself, so this is only used when the
+    /// listening socket receives the decrypted traffic from a local TLS terminator.
+    /// The value is available via `ConnectionInfo::is_secure()`, and the default
+    /// value is `false`.
+    pub fn secure(self, secure: bool) -> Self {
+        Launcher { secure, ..self }
+    }
+
+    /// Sets the time limit for receiving the request headers.
+    ///
+    /// The timer starts when a connection is accepted and each time a response has been
+    /// sent, and the connection is closed if the headers of the next request have not
+    /// been received in time. This also limits how long an idle keep-alive connection
+    /// is held.
+    pub fn header_read_timeout(self, timeout: Duration) -> Self {
+        Launcher {
+            header_read_timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Sets the time limit for receiving the message body of each request.
+    ///
+    /// If the limit elapses while reading the body, the endpoint receives an error
+    /// with the status code `408 Request Timeout`.
+    pub fn body_read_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.body_read = Some(timeout);
+        self
+    }
+
+    /// Sets the time limit for processing each request.
+    ///
+    /// If the limit elapses before the endpoint completes, the server responds
+    /// with the status code `503 Service Unavailable`.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.request = Some(timeout);
+        self
+    }
+
+    /// Registers a value shared by the whole application.
+    ///
+    /// The registered value can be accessed from the endpoints with `endpoints::state::<T>()`.
+    /// If a value of the same type has already been registered, it is replaced.
+    pub fn manage<T>(mut self, value: T) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        self.state.insert(value);
+        self
+    }
+
     /// Start the server with binding the specified listener address.
     pub fn start(self, addr: impl ToSocketAddrs) {
         if let Err(err) = self.start_inner(addr) {
@@ -115,13 +206,28 @@ where
     }
 
     fn start_inner(self, addr: impl ToSocketAddrs) -> Fallible<()> {
-        let Launcher { endpoint, rt, http } = self;
+        let Launcher {
+            endpoint,
+            rt,
+            http,
+            state,
+            header_read_timeout,
+            timeouts,
+            tcp_nodelay,
+            tcp_keepalive,
+            secure,
+        } = self;
 
         let addr = addr
             .to_socket_addrs()?
             .next()
             .ok_or_else(|| err_msg("empty listener address"))?;
-        let incoming = TcpListener::bind(&addr)?.incoming();
+        let incoming = AcceptBackoff {
+            incoming: TcpListener::bind(&addr)?.incoming(),
+            delay: None,
+            tcp_nodelay,
+            tcp_keepalive,
+        };
 
         // Acquire a `'static` reference to the target endpoint.
         //
@@ -129,12 +235,44 @@ where
         // with Tokio runtime.
         let endpoint = endpoint.into_endpoint();
         let endpoint: &'static _ = unsafe { &*(&endpoint as *const _) };
-        let new_service = App::new(endpoint);
 
+        // Drive each accepted connection individually so that the services
+        // can receive the information of the underlying connection.
         let http = http.unwrap_or_else(Http::new);
-        let server = Builder::new(incoming, http)
-            .serve(new_service)
-            .map_err(|err| error!("server error: {}", err));
+        let state = Arc::new(state);
+        let server = incoming.for_each(move |stream| {
+            let connection =
+                ConnectionInfo::new(stream.peer_addr().ok(), stream.local_addr().ok(), secure);
+            let app = App::with_connection(endpoint, connection)
+                .with_state(state.clone())
+                .with_timeouts(timeouts);
+            match header_read_timeout {
+                Some(timeout) => {
+                    let conn_state = Arc::new(ConnState::default());
+                    let service = TrackInFlight {
+                        app,
+                        state: conn_state.clone(),
+                    };
+                    let conn = http
+                        .serve_connection(stream, service)
+                        .map_err(|err| error!("connection error: {}", err));
+                    tokio::spawn(HeaderReadTimeout {
+                        conn,
+                        state: conn_state,
+                        timeout,
+                        delay: None,
+                        received: 0,
+                    });
+                }
+                None => {
+                    let conn = http
+                        .serve_connection(stream, app)
+                        .map_err(|err| error!("connection error: {}", err));
+                    tokio::spawn(conn);
+                }
+            }
+            Ok(())
+        });
 
         let mut rt = match rt {
             Some(rt) => rt,
@@ -147,6 +285,236 @@ where
     }
 }
 
+/// A stream of accepted connections which pauses accepting for a while after an error.
+///
+/// The errors such as `EMFILE` are likely to occur again immediately, so retrying
+/// without delay results in a busy loop.
+struct AcceptBackoff {
+    incoming: Incoming,
+    delay: Option<Delay>,
+    tcp_nodelay: bool,
+    tcp_keepalive: Option<Duration>,
+}
+
+impl AcceptBackoff {
+    fn configure(&self, stream: &TcpStream) -> io::Result<()> {
+        stream.set_nodelay(self.tcp_nodelay)?;
+        stream.set_keepalive(self.tcp_keepalive)?;
+        Ok(())
+    }
+}
+
+impl Stream for AcceptBackoff {
+    type Item = TcpStream;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<TcpStream>, ()> {
+        if let Some(ref mut delay) = self.delay {
+            match delay.poll() {
+                Ok(Async::Ready(())) => {}
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Err(err) => error!("timer error: {}", err),
+            }
+        }
+        self.delay = None;
+
+        loop {
+            match self.incoming.poll() {
+                Ok(Async::Ready(Some(stream))) => {
+                    if let Err(err) = self.configure(&stream) {
+                        error!("failed to configure the connection: {}", err);
+                    }
+                    return Ok(Async::Ready(Some(stream)));
+                }
+                Ok(Async::Ready(None)) => return Ok(Async::Ready(None)),
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                // The connection was closed by the peer before being accepted.
+                Err(ref err) if is_connection_error(err) => continue,
+                Err(err) => {
+                    error!("accept error: {}", err);
+                    let mut delay = Delay::new(Instant::now() + Duration::from_secs(1));
+                    match delay.poll() {
+                        Ok(Async::NotReady) => {
+                            self.delay = Some(delay);
+                            return Ok(Async::NotReady);
+                        }
+                        Ok(Async::Ready(())) => continue,
+                        Err(err) => {
+                            error!("timer error: {}", err);
+                            continue;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn is_connection_error(err: &io::Error) -> bool {
+    match err.kind() {
+        io::ErrorKind::ConnectionRefused
+        | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::ConnectionReset => true,
+        _ => false,
+    }
+}
+
+/// The state of a connection shared between the service and `HeaderReadTimeout`.
+#[derive(Debug, Default)]
+struct ConnState {
+    /// The number of requests whose response has not been sent yet.
+    in_flight: AtomicUsize,
+    /// The number of requests received on the connection.
+    received: AtomicUsize,
+}
+
+/// A guard which marks a request as in flight until it is dropped.
+struct InFlight(Arc<ConnState>);
+
+impl InFlight {
+    fn new(state: Arc<ConnState>) -> InFlight {
+        state.in_flight.fetch_add(1, Ordering::SeqCst);
+        state.received.fetch_add(1, Ordering::SeqCst);
+        InFlight(state)
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A service which tracks the requests in flight on a connection.
+struct TrackInFlight<S> {
+    app: S,
+    state: Arc<ConnState>,
+}
+
+impl<S> Service for TrackInFlight<S>
+where
+    S: Service<ReqBody = Body>,
+{
+    type ReqBody = Body;
+    type ResBody = TrackedBody<S::ResBody>;
+    type Error = S::Error;
+    type Future = TrackedFuture<S::Future>;
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let guard = InFlight::new(self.state.clone());
+        TrackedFuture {
+            future: self.app.call(request),
+            guard: Some(guard),
+        }
+    }
+}
+
+struct TrackedFuture<F> {
+    future: F,
+    guard: Option<InFlight>,
+}
+
+impl<F, B> Future for TrackedFuture<F>
+where
+    F: Future<Item = Response<B>>,
+{
+    type Item = Response<TrackedBody<B>>;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let response = try_ready!(self.future.poll());
+        let guard = self.guard.take();
+        Ok(Async::Ready(response.map(|body| TrackedBody {
+            body,
+            _guard: guard,
+        })))
+    }
+}
+
+/// A response body which keeps the request in flight until the body has been sent.
+struct TrackedBody<B> {
+    body: B,
+    _guard: Option<InFlight>,
+}
+
+impl<B: Payload> Payload for TrackedBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_data(&mut self) -> Poll<Option<Self::Data>, Self::Error> {
+        self.body.poll_data()
+    }
+
+    fn poll_trailers(&mut self) -> Poll<Option<HeaderMap>, Self::Error> {
+        self.body.poll_trailers()
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        self.body.content_length()
+    }
+}
+
+/// A future which closes the connection if the headers of the next request
+/// have not been received within the time limit.
+///
+/// The timer is armed while no request is in flight, i.e. after the connection
+/// is accepted and after each response has been sent.
+struct HeaderReadTimeout<F> {
+    conn: F,
+    state: Arc<ConnState>,
+    timeout: Duration,
+    delay: Option<Delay>,
+    received: usize,
+}
+
+impl<F> Future for HeaderReadTimeout<F>
+where
+    F: Future<Item = (), Error = ()>,
+{
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        if let Async::Ready(()) = self.conn.poll()? {
+            return Ok(Async::Ready(()));
+        }
+
+        if self.state.in_flight.load(Ordering::SeqCst) > 0 {
+            self.delay = None;
+            return Ok(Async::NotReady);
+        }
+
+        // Re-arm the timer if a request has been received and completed since the last poll.
+        let received = self.state.received.load(Ordering::SeqCst);
+        if self.delay.is_none() || self.received != received {
+            self.delay = Some(Delay::new(Instant::now() + self.timeout));
+            self.received = received;
+        }
+
+        let expired = match self.delay {
+            Some(ref mut delay) => match delay.poll() {
+                Ok(Async::Ready(())) => true,
+                Ok(Async::NotReady) => false,
+                Err(err) => {
+                    error!("timer error: {}", err);
+                    true
+                }
+            },
+            None => false,
+        };
+        if expired {
+            error!("timed out while reading the request headers");
+            return Ok(Async::Ready(()));
+        }
+
+        Ok(Async::NotReady)
+    }
+}
+
 /// Create an instance of `Launcher` from the specified endpoint.
 ///
 /// # Example
@@ -168,5 +536,71 @@ where
         endpoint,
         http: None,
         rt: None,
+        state: StateMap::default(),
+        header_read_timeout: None,
+        timeouts: Timeouts::default(),
+        tcp_nodelay: true,
+        tcp_keepalive: None,
+        secure: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::{self, Either};
+    use tokio::runtime::current_thread::Runtime;
+
+    fn header_read_timeout<F>(conn: F, state: Arc<ConnState>) -> HeaderReadTimeout<F> {
+        HeaderReadTimeout {
+            conn,
+            state,
+            timeout: Duration::from_millis(20),
+            delay: None,
+            received: 0,
+        }
+    }
+
+    #[test]
+    fn test_header_read_timeout_idle() {
+        let mut rt = Runtime::new().unwrap();
+        let state = Arc::new(ConnState::default());
+        let future = header_read_timeout(future::empty(), state);
+        assert!(rt.block_on(future).is_ok());
+    }
+
+    #[test]
+    fn test_header_read_timeout_in_flight() {
+        let mut rt = Runtime::new().unwrap();
+        let state = Arc::new(ConnState::default());
+        let _guard = InFlight::new(state.clone());
+        let future = header_read_timeout(future::empty(), state)
+            .select2(Delay::new(Instant::now() + Duration::from_millis(50)));
+        match rt.block_on(future) {
+            Ok(Either::B(..)) => {}
+            _ => panic!("the connection must not be closed while a request is in flight"),
+        }
+    }
+
+    #[test]
+    fn test_header_read_timeout_rearmed_after_response() {
+        let mut rt = Runtime::new().unwrap();
+        let state = Arc::new(ConnState::default());
+
+        // A connection which sends the response of a request after 30ms.
+        let mut guard = Some(InFlight::new(state.clone()));
+        let mut response = Delay::new(Instant::now() + Duration::from_millis(30));
+        let conn = future::poll_fn(move || {
+            if guard.is_some() {
+                if let Async::Ready(()) = response.poll().map_err(|_| ())? {
+                    guard = None;
+                }
+            }
+            Ok(Async::NotReady)
+        });
+
+        let start = Instant::now();
+        assert!(rt.block_on(header_read_timeout(conn, state)).is_ok());
+        assert!(start.elapsed() >= Duration::from_millis(50));
     }
 }
@@ -57,6 +57,8 @@
 #![cfg_attr(feature = "strict", deny(warnings))]
 #![cfg_attr(feature = "strict", doc(test(attr(deny(warnings)))))]
 
+#[cfg(feature = "jwt")]
+extern crate base64;
 extern crate bitflags;
 extern crate bytes;
 extern crate cookie;
@@ -64,6 +66,8 @@ extern crate failure;
 extern crate futures;      // 0.1
 extern crate futures_core; // 0.3
 extern crate futures_util; // 0.3
+#[cfg(feature = "handlebars")]
+extern crate handlebars;
 extern crate http;
 extern crate hyper;
 extern crate hyperx;
@@ -72,11 +76,19 @@ extern crate mime;
 extern crate mime_guess;
 extern crate percent_encoding;
 extern crate pin_utils;
+extern crate rand;
+extern crate regex;
+#[cfg(feature = "jwt")]
+extern crate ring;
 extern crate serde;
 extern crate serde_json;
 extern crate serde_qs;
+#[cfg(feature = "tera")]
+extern crate tera;
 extern crate time;
 extern crate tokio;
+#[cfg(feature = "jwt")]
+extern crate untrusted;
 extern crate url;
 
 #[cfg(test)]
@@ -105,4 +117,5 @@ pub mod prelude {
     pub use crate::endpoint::{Endpoint, IntoEndpoint, IntoEndpointExt, SendEndpoint};
     pub use crate::endpoints;
     pub use crate::error::HttpError;
+    pub use crate::output::OutputExt;
 }
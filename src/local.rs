@@ -56,7 +56,10 @@
 
 use std::borrow::Cow;
 use std::mem;
+use std::net::SocketAddr;
 use std::pin::{PinBox, PinMut};
+use std::sync::Arc;
+use std::time::Duration;
 
 use futures::future as future01;
 use futures::stream as stream01;
@@ -73,10 +76,12 @@ use http::{HttpTryFrom, Method, Request, Response, Uri};
 use hyper::body::Body;
 use tokio::runtime::current_thread::Runtime;
 
-use crate::app::App;
+use crate::app::{App, Timeouts};
+use crate::common::StateMap;
 use crate::endpoint::Endpoint;
 use crate::error::{Error, Never};
 use crate::input::body::ReqBody;
+use crate::input::ConnectionInfo;
 use crate::output::payload::Payload;
 use crate::output::Output;
 
@@ -92,6 +97,7 @@ macro_rules! impl_constructors {
         {
             (LocalRequest {
                 request: Some(Request::new(ReqBody::from_hyp(Default::default()))),
+                timeouts: Timeouts::default(),
             })
             .method(Method::$METHOD)
             .uri(uri)
@@ -123,6 +129,7 @@ impl_constructors! {
 #[derive(Debug)]
 pub struct LocalRequest {
     request: Option<Request<ReqBody>>,
+    timeouts: Timeouts,
 }
 
 impl LocalRequest {
@@ -171,6 +178,52 @@ impl LocalRequest {
         self
     }
 
+    /// Sets the socket address of the client which sends this dummy request.
+    pub fn remote_addr(self, addr: impl Into<SocketAddr>) -> Self {
+        let addr = addr.into();
+        self.with_connection(|conn| {
+            ConnectionInfo::new(Some(addr), conn.local_addr(), conn.is_secure())
+        })
+    }
+
+    /// Sets whether this dummy request is received on a connection secured with TLS.
+    pub fn secure(self, secure: bool) -> Self {
+        self.with_connection(|conn| {
+            ConnectionInfo::new(conn.remote_addr(), conn.local_addr(), secure)
+        })
+    }
+
+    fn with_connection(mut self, f: impl FnOnce(ConnectionInfo) -> ConnectionInfo) -> Self {
+        if let Some(ref mut request) = self.request {
+            let conn = request
+                .extensions()
+                .get::<ConnectionInfo>()
+                .cloned()
+                .unwrap_or_else(|| ConnectionInfo::new(None, None, false));
+            request.extensions_mut().insert(f(conn));
+        }
+        self
+    }
+
+    /// Registers a value which can be accessed with `endpoints::state::<T>()`.
+    ///
+    /// This is used for injecting the substitutes of the application-wide state
+    /// (e.g. mock database connections) in the tests.
+    pub fn state<T>(mut self, value: T) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        if let Some(ref mut request) = self.request {
+            let mut state = request
+                .extensions_mut()
+                .remove::<Arc<StateMap>>()
+                .unwrap_or_default();
+            Arc::make_mut(&mut state).insert(value);
+            request.extensions_mut().insert(state);
+        }
+        self
+    }
+
     /// Overwrite the message body of this dummy request with given instance.
     pub fn body(mut self, body: impl Into<Body>) -> Self {
         if let Some(ref mut request) = self.request {
@@ -179,12 +232,27 @@ impl LocalRequest {
         self
     }
 
+    /// Sets the time limit for reading the message body, as `Launcher::body_read_timeout()`.
+    pub fn body_read_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.body_read = Some(timeout);
+        self
+    }
+
+    /// Sets the time limit for the whole of request, as `Launcher::request_timeout()`.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.request = Some(timeout);
+        self
+    }
+
     /// Apply this dummy request to the associated endpoint and get its response.
     pub fn apply<'e, E: Endpoint<'e>>(self, endpoint: &'e E) -> Result<E::Output, Error> {
-        let LocalRequest { mut request } = self;
+        let LocalRequest {
+            mut request,
+            timeouts,
+        } = self;
         let request = request.take().expect("The request has already applied");
 
-        let app = App::new(endpoint);
+        let app = App::new(endpoint).with_timeouts(timeouts);
 
         let mut future = app.dispatch_request(request);
         let future = poll_fn(move |cx| {
@@ -202,10 +270,13 @@ impl LocalRequest {
         E: Endpoint<'e>,
         E::Output: Output,
     {
-        let LocalRequest { mut request } = self;
+        let LocalRequest {
+            mut request,
+            timeouts,
+        } = self;
         let request = request.take().expect("The request has already applied");
 
-        let app = App::new(endpoint);
+        let app = App::new(endpoint).with_timeouts(timeouts);
 
         let mut future = app.dispatch_request(request);
         let future = poll_fn(move |cx| {
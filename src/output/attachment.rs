@@ -0,0 +1,157 @@
+use std::error::Error as StdError;
+use std::fmt::Write;
+
+use bytes::Bytes;
+use futures::Stream;
+use http::header::HeaderValue;
+use http::{header, Response};
+use hyper::Chunk;
+use mime::Mime;
+use tokio::prelude::AsyncRead;
+
+use super::payload::{Body, Once, Payload, ReaderStream};
+use super::{Output, OutputContext};
+use crate::error::{fail, Error};
+
+/// An instance of `Output` representing a downloadable content.
+///
+/// The response has the header `Content-Disposition: attachment`, with the
+/// file name encoded according to RFC 6266.
+#[derive(Debug)]
+pub struct Attachment<B> {
+    body: B,
+    filename: Option<String>,
+    content_type: Mime,
+    content_length: Option<u64>,
+}
+
+impl Attachment<Once<Bytes>> {
+    /// Creates an `Attachment` from the in-memory data.
+    pub fn from_bytes(data: impl Into<Bytes>) -> Attachment<Once<Bytes>> {
+        let data = data.into();
+        let len = data.len() as u64;
+        Attachment::new(Once::new(data)).content_length(len)
+    }
+}
+
+impl<R> Attachment<ReaderStream<R>>
+where
+    R: AsyncRead + Send + 'static,
+{
+    /// Creates an `Attachment` whose body is read from the specified reader.
+    pub fn from_reader(reader: R) -> Attachment<ReaderStream<R>> {
+        Attachment::new(ReaderStream::new(reader))
+    }
+}
+
+impl Attachment<Body> {
+    /// Creates an `Attachment` whose body is the specified stream of byte chunks.
+    pub fn from_stream<S>(stream: S) -> Attachment<Body>
+    where
+        S: Stream + Send + 'static,
+        S::Error: Into<Box<dyn StdError + Send + Sync>>,
+        Chunk: From<S::Item>,
+    {
+        Attachment::new(Body::wrap_stream(stream))
+    }
+}
+
+impl<B> Attachment<B> {
+    /// Creates an `Attachment` with the specified body.
+    ///
+    /// The content type is set to `application/octet-stream` by default.
+    pub fn new(body: B) -> Attachment<B> {
+        Attachment {
+            body,
+            filename: None,
+            content_type: mime::APPLICATION_OCTET_STREAM,
+            content_length: None,
+        }
+    }
+
+    /// Sets the file name suggested to the client.
+    pub fn filename(self, filename: impl Into<String>) -> Attachment<B> {
+        Attachment {
+            filename: Some(filename.into()),
+            ..self
+        }
+    }
+
+    /// Sets the MIME type of the content.
+    pub fn content_type(self, content_type: Mime) -> Attachment<B> {
+        Attachment {
+            content_type,
+            ..self
+        }
+    }
+
+    /// Sets the length of the content, if it is known in advance.
+    pub fn content_length(self, len: u64) -> Attachment<B> {
+        Attachment {
+            content_length: Some(len),
+            ..self
+        }
+    }
+}
+
+impl<B: Payload> Output for Attachment<B> {
+    type Body = B;
+    type Error = Error;
+
+    fn respond(self, _: &mut OutputContext<'_>) -> Result<Response<Self::Body>, Self::Error> {
+        let disposition = content_disposition(self.filename.as_ref().map(|s| &**s));
+        let disposition = HeaderValue::from_str(&disposition).map_err(fail)?;
+        let content_type = HeaderValue::from_str(self.content_type.as_ref()).map_err(fail)?;
+
+        let mut response = Response::new(self.body);
+        response
+            .headers_mut()
+            .insert(header::CONTENT_DISPOSITION, disposition);
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, content_type);
+        if let Some(len) = self.content_length {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+        }
+        Ok(response)
+    }
+}
+
+/// Builds the value of `Content-Disposition`.
+///
+/// The parameter `filename` is an ASCII fallback for the legacy clients,
+/// and `filename*` contains the original name encoded as in RFC 5987.
+fn content_disposition(filename: Option<&str>) -> String {
+    let filename = match filename {
+        Some(filename) => filename,
+        None => return "attachment".into(),
+    };
+
+    let fallback: String = filename
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' => '_',
+            c if c.is_ascii() && !c.is_ascii_control() => c,
+            _ => '_',
+        }).collect();
+
+    let mut value = format!("attachment; filename=\"{}\"; filename*=UTF-8''", fallback);
+    for &b in filename.as_bytes() {
+        if is_attr_char(b) {
+            value.push(b as char);
+        } else {
+            let _ = write!(value, "%{:02X}", b);
+        }
+    }
+    value
+}
+
+fn is_attr_char(b: u8) -> bool {
+    match b {
+        b'a'...b'z' | b'A'...b'Z' | b'0'...b'9' => true,
+        b'!' | b'#' | b'$' | b'&' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~' => true,
+        _ => false,
+    }
+}
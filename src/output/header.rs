@@ -0,0 +1,116 @@
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use http::{HttpTryFrom, Response};
+use hyperx::header::Header;
+use std::fmt;
+
+use super::{Output, OutputContext};
+use crate::error::{fail, Error};
+
+/// A helper trait for attaching header fields to the responses of any `Output`.
+pub trait OutputExt: Output {
+    /// Creates an instance of `WithHeaders` which appends a header field to the response.
+    fn with_header<K, V>(self, name: K, value: V) -> WithHeaders<Self>
+    where
+        HeaderName: HttpTryFrom<K>,
+        HeaderValue: HttpTryFrom<V>,
+    {
+        WithHeaders::new(self).with_header(name, value)
+    }
+
+    /// Creates an instance of `WithHeaders` which appends a typed header to the response.
+    fn with_typed_header<H>(self, header: H) -> WithHeaders<Self>
+    where
+        H: Header + fmt::Display,
+    {
+        WithHeaders::new(self).with_typed_header(header)
+    }
+}
+
+impl<T: Output> OutputExt for T {}
+
+/// An instance of `Output` which adds the header fields to the response of the inner value.
+///
+/// The header fields set by the inner value are replaced by the ones with the same name.
+#[derive(Debug)]
+pub struct WithHeaders<T> {
+    value: T,
+    headers: HeaderMap,
+    error: Option<http::Error>,
+}
+
+impl<T> WithHeaders<T> {
+    /// Creates a new `WithHeaders` with the specified value and no header fields.
+    pub fn new(value: T) -> WithHeaders<T> {
+        WithHeaders {
+            value,
+            headers: HeaderMap::new(),
+            error: None,
+        }
+    }
+
+    /// Appends a header field.
+    ///
+    /// If the name or the value is invalid, the error is reported when creating the response.
+    pub fn with_header<K, V>(self, name: K, value: V) -> WithHeaders<T>
+    where
+        HeaderName: HttpTryFrom<K>,
+        HeaderValue: HttpTryFrom<V>,
+    {
+        let name = HeaderName::try_from(name).map_err(Into::into);
+        let value = HeaderValue::try_from(value).map_err(Into::into);
+        self.append(name, value)
+    }
+
+    /// Appends a typed header field.
+    pub fn with_typed_header<H>(self, header: H) -> WithHeaders<T>
+    where
+        H: Header + fmt::Display,
+    {
+        let name = HeaderName::from_bytes(H::header_name().as_bytes()).map_err(Into::into);
+        let value = HeaderValue::from_str(&header.to_string()).map_err(Into::into);
+        self.append(name, value)
+    }
+
+    /// Returns a reference to the header fields to be added.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns a mutable reference to the header fields to be added.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    fn append(
+        mut self,
+        name: Result<HeaderName, http::Error>,
+        value: Result<HeaderValue, http::Error>,
+    ) -> WithHeaders<T> {
+        if self.error.is_none() {
+            match name.and_then(|name| value.map(|value| (name, value))) {
+                Ok((name, value)) => {
+                    self.headers.append(name, value);
+                }
+                Err(err) => self.error = Some(err),
+            }
+        }
+        self
+    }
+}
+
+impl<T: Output> Output for WithHeaders<T> {
+    type Body = T::Body;
+    type Error = Error;
+
+    fn respond(self, cx: &mut OutputContext<'_>) -> Result<Response<Self::Body>, Self::Error> {
+        if let Some(err) = self.error {
+            return Err(fail(err));
+        }
+        let mut response = self.value.respond(cx).map_err(Into::into)?;
+        for name in self.headers.keys() {
+            response.headers_mut().remove(name);
+        }
+        response.headers_mut().extend(self.headers);
+        Ok(response)
+    }
+}
@@ -3,10 +3,14 @@
 pub mod fs;
 pub mod payload;
 pub mod status;
+pub mod template;
 
+mod attachment;
 mod binary;
 mod debug;
+mod header;
 mod json;
+mod redirect;
 mod text;
 
 use http::{Response, StatusCode};
@@ -22,10 +26,13 @@ use crate::input::Input;
 
 use self::payload::Empty;
 
+pub use self::attachment::Attachment;
 pub use self::binary::Binary;
 pub use self::debug::Debug;
 pub use self::fs::NamedFile;
+pub use self::header::{OutputExt, WithHeaders};
 pub use self::json::Json;
+pub use self::redirect::Redirect;
 pub use self::text::Text;
 
 /// Contextual information at applying `Output::respond`.
@@ -1,7 +1,9 @@
 //! Implementors of `Payload`.
 
-use futures::{self, Async};
+use bytes::{BufMut, Bytes, BytesMut};
+use futures::{self, try_ready, Async};
 use std::io;
+use tokio::prelude::AsyncRead;
 
 pub use hyper::body::{Body, Payload};
 
@@ -49,3 +51,41 @@ impl<T: AsRef<[u8]> + Send + 'static> Payload for Once<T> {
         self.0.as_ref().map(|body| body.as_ref().len() as u64)
     }
 }
+
+/// A `Payload` which reads the data from an asynchronous reader.
+#[derive(Debug)]
+pub struct ReaderStream<R> {
+    reader: R,
+    buf: BytesMut,
+    buf_size: usize,
+}
+
+impl<R> ReaderStream<R> {
+    /// Creates a `ReaderStream` from the specified reader.
+    pub fn new(reader: R) -> ReaderStream<R> {
+        ReaderStream {
+            reader,
+            buf: BytesMut::new(),
+            buf_size: 8192,
+        }
+    }
+}
+
+impl<R: AsyncRead + Send + 'static> Payload for ReaderStream<R> {
+    type Data = io::Cursor<Bytes>;
+    type Error = io::Error;
+
+    fn poll_data(&mut self) -> futures::Poll<Option<Self::Data>, Self::Error> {
+        if self.buf.remaining_mut() < self.buf_size {
+            self.buf.reserve(self.buf_size);
+        }
+
+        match try_ready!(self.reader.read_buf(&mut self.buf)) {
+            0 => Ok(Async::Ready(None)),
+            _ => {
+                let chunk = self.buf.take().freeze();
+                Ok(Async::Ready(Some(io::Cursor::new(chunk))))
+            }
+        }
+    }
+}
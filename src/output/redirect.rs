@@ -0,0 +1,73 @@
+use http::header::HeaderValue;
+use http::{header, Response, StatusCode};
+use std::borrow::Cow;
+
+use super::payload::Empty;
+use super::{Output, OutputContext};
+use crate::error::{fail, Error};
+
+/// An instance of `Output` representing redirect responses.
+#[derive(Debug, Clone)]
+pub struct Redirect {
+    status: StatusCode,
+    location: Cow<'static, str>,
+}
+
+impl Redirect {
+    /// Creates a `Redirect` with the status code `302 Found`.
+    pub fn to(location: impl Into<Cow<'static, str>>) -> Redirect {
+        Redirect::new(StatusCode::FOUND, location)
+    }
+
+    /// Creates a `Redirect` with the status code `308 Permanent Redirect`.
+    ///
+    /// The client should use the new URI for the future requests, without changing the method.
+    pub fn permanent(location: impl Into<Cow<'static, str>>) -> Redirect {
+        Redirect::new(StatusCode::PERMANENT_REDIRECT, location)
+    }
+
+    /// Creates a `Redirect` with the status code `303 See Other`.
+    ///
+    /// The client should retrieve the new URI with `GET`, typically after submitting a form.
+    pub fn see_other(location: impl Into<Cow<'static, str>>) -> Redirect {
+        Redirect::new(StatusCode::SEE_OTHER, location)
+    }
+
+    /// Creates a `Redirect` with the status code `307 Temporary Redirect`.
+    ///
+    /// The client should repeat the request to the new URI, without changing the method.
+    pub fn temporary(location: impl Into<Cow<'static, str>>) -> Redirect {
+        Redirect::new(StatusCode::TEMPORARY_REDIRECT, location)
+    }
+
+    fn new(status: StatusCode, location: impl Into<Cow<'static, str>>) -> Redirect {
+        Redirect {
+            status,
+            location: location.into(),
+        }
+    }
+
+    /// Returns the status code of this redirect.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the value of `Location` header.
+    pub fn location(&self) -> &str {
+        &*self.location
+    }
+}
+
+impl Output for Redirect {
+    type Body = Empty;
+    type Error = Error;
+
+    fn respond(self, _: &mut OutputContext<'_>) -> Result<Response<Self::Body>, Self::Error> {
+        let location = HeaderValue::from_str(&self.location).map_err(fail)?;
+
+        let mut response = Response::new(Empty);
+        *response.status_mut() = self.status;
+        response.headers_mut().insert(header::LOCATION, location);
+        Ok(response)
+    }
+}
@@ -0,0 +1,183 @@
+//! Components for rendering templates.
+//!
+//! The template engine is registered as the application-wide state by using
+//! `Launcher::manage(Renderer::new(engine))`, and the handlers return a `Template`
+//! which holds the name of template and the context to be passed to the engine.
+//!
+//! The adapters for `tera` and `handlebars` are provided when the corresponding
+//! cargo features are enabled.
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate failure;
+//! # extern crate finchers;
+//! # #[macro_use]
+//! # extern crate serde_json;
+//! use finchers::output::template::{Renderer, Template};
+//! use finchers::path;
+//! use finchers::prelude::*;
+//!
+//! # fn render(name: &str, _: &serde_json::Value) -> Result<String, failure::Error> {
+//! #     Ok(name.to_owned())
+//! # }
+//! fn main() {
+//!     let endpoint = path!(@get / "users" / String)
+//!         .map(|name: String| Template::new("user.html", json!({ "name": name })));
+//!
+//!     let launcher = finchers::launch(endpoint)
+//!         .manage(Renderer::new(render));
+//! #   drop(launcher);
+//! }
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+use failure;
+use http::header::HeaderValue;
+use http::{header, Response, StatusCode};
+use mime::Mime;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::payload::Once;
+use super::{Output, OutputContext};
+use crate::common::StateMap;
+use crate::error::{err_msg, fail, Error};
+
+/// A trait representing the template engines.
+pub trait TemplateEngine: Send + Sync + 'static {
+    /// Renders the template with the specified name and context.
+    fn render(&self, name: &str, context: &Value) -> Result<String, failure::Error>;
+}
+
+impl<F> TemplateEngine for F
+where
+    F: Fn(&str, &Value) -> Result<String, failure::Error> + Send + Sync + 'static,
+{
+    fn render(&self, name: &str, context: &Value) -> Result<String, failure::Error> {
+        (*self)(name, context)
+    }
+}
+
+#[cfg(feature = "tera")]
+impl TemplateEngine for tera::Tera {
+    fn render(&self, name: &str, context: &Value) -> Result<String, failure::Error> {
+        tera::Tera::render(self, name, context).map_err(|err| failure::SyncFailure::new(err).into())
+    }
+}
+
+#[cfg(feature = "handlebars")]
+impl TemplateEngine for handlebars::Handlebars {
+    fn render(&self, name: &str, context: &Value) -> Result<String, failure::Error> {
+        handlebars::Handlebars::render(self, name, context)
+            .map_err(|err| failure::SyncFailure::new(err).into())
+    }
+}
+
+/// The application-wide template renderer, used by `Template`.
+pub struct Renderer {
+    engine: Box<dyn TemplateEngine>,
+}
+
+impl fmt::Debug for Renderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Renderer").finish()
+    }
+}
+
+impl Renderer {
+    /// Creates a `Renderer` with the specified template engine.
+    pub fn new(engine: impl TemplateEngine) -> Renderer {
+        Renderer {
+            engine: Box::new(engine),
+        }
+    }
+
+    /// Creates a `Renderer` which reloads the templates at every rendering.
+    ///
+    /// The function `loader` is called before rendering each template, so the
+    /// changes to the template files are applied without restarting the server.
+    /// This mode is intended for use in development.
+    pub fn reloading<F, E>(loader: F) -> Renderer
+    where
+        F: Fn() -> Result<E, failure::Error> + Send + Sync + 'static,
+        E: TemplateEngine,
+    {
+        Renderer::new(move |name: &str, context: &Value| loader()?.render(name, context))
+    }
+
+    /// Renders the template with the specified name and context.
+    pub fn render(&self, name: &str, context: &Value) -> Result<String, failure::Error> {
+        self.engine.render(name, context)
+    }
+}
+
+/// An instance of `Output` representing a rendered template.
+///
+/// The rendering errors are converted into `500 Internal Server Error`.
+#[derive(Debug)]
+pub struct Template {
+    name: Cow<'static, str>,
+    context: Result<Value, serde_json::Error>,
+    content_type: Mime,
+}
+
+impl Template {
+    /// Creates a `Template` with the specified template name and context.
+    ///
+    /// The content type is set to `text/html; charset=utf-8` by default.
+    pub fn new(name: impl Into<Cow<'static, str>>, context: impl Serialize) -> Template {
+        Template {
+            name: name.into(),
+            context: serde_json::to_value(context),
+            content_type: mime::TEXT_HTML_UTF_8,
+        }
+    }
+
+    /// Sets the content type of the rendered content.
+    pub fn content_type(self, content_type: Mime) -> Template {
+        Template {
+            content_type,
+            ..self
+        }
+    }
+
+    /// Returns the name of template.
+    pub fn name(&self) -> &str {
+        &*self.name
+    }
+}
+
+impl Output for Template {
+    type Body = Once<String>;
+    type Error = Error;
+
+    fn respond(self, cx: &mut OutputContext<'_>) -> Result<Response<Self::Body>, Self::Error> {
+        let renderer = cx
+            .input()
+            .extensions()
+            .get::<Arc<StateMap>>()
+            .and_then(|state| state.get::<Renderer>())
+            .ok_or_else(|| {
+                err_msg(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "the template renderer is not registered",
+                )
+            })?;
+
+        let context = self.context.map_err(fail)?;
+        let body = renderer
+            .render(&self.name, &context)
+            .map_err(|err| err_msg(StatusCode::INTERNAL_SERVER_ERROR, err))?;
+        let content_type = HeaderValue::from_str(self.content_type.as_ref()).map_err(fail)?;
+
+        let mut response = Response::new(Once::new(body));
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, content_type);
+        Ok(response)
+    }
+}
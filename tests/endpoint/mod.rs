@@ -4,9 +4,11 @@ mod boxed;
 mod impl_endpoint;
 mod macros;
 mod map;
+mod mount;
 mod or;
 mod or_strict;
 mod recover;
+mod router;
 mod syntax;
 mod then;
 mod wrap;
@@ -0,0 +1,91 @@
+use finchers::endpoint::syntax;
+use finchers::endpoint::MountPoint;
+use finchers::error::Error;
+use finchers::local;
+use finchers::prelude::*;
+use finchers::{named_path, path};
+use futures_util::future::ready;
+use matches::assert_matches;
+
+#[test]
+fn test_mount() {
+    let v1 = path!(@get / "posts" / u32 /).map(|id: u32| format!("v1: {}", id));
+    let v2 = path!(@get / "posts" / u32 /).map(|id: u32| format!("v2: {}", id));
+    let endpoint = endpoint::mount("/api/v1", v1).or_strict(endpoint::mount("api/v2/", v2));
+
+    assert_matches!(
+        local::get("/api/v1/posts/42").apply(&endpoint),
+        Ok((ref s,)) if s == "v1: 42"
+    );
+    assert_matches!(
+        local::get("/api/v2/posts/42").apply(&endpoint),
+        Ok((ref s,)) if s == "v2: 42"
+    );
+    assert_matches!(
+        local::get("/api/v3/posts/42").apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 404
+    );
+    assert_matches!(
+        local::post("/api/v1/posts/42").apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 405
+    );
+}
+
+#[test]
+fn test_mount_remaining_path() {
+    let endpoint = endpoint::mount("api", syntax::remains::<String>());
+
+    assert_matches!(
+        local::get("/api/posts/42").apply(&endpoint),
+        Ok((ref s,)) if s == "posts/42"
+    );
+}
+
+#[test]
+fn test_mount_point() {
+    let mount_point =
+        || endpoint::apply_fn(|cx| Ok(ready(Ok::<_, Error>((cx.mount_point().to_owned(),)))));
+
+    assert_matches!(
+        local::get("/api/v1/posts").apply(&mount_point()),
+        Ok((ref s,)) if s == ""
+    );
+
+    let endpoint = endpoint::mount("api/v1", mount_point());
+    assert_matches!(
+        local::get("/api/v1/posts").apply(&endpoint),
+        Ok((ref s,)) if s == "/api/v1"
+    );
+    assert_matches!(
+        local::get("/api/v1").apply(&endpoint),
+        Ok((ref s,)) if s == "/api/v1"
+    );
+
+    let endpoint = endpoint::mount("api", endpoint::mount("v2", mount_point()));
+    assert_matches!(
+        local::get("/api/v2/posts").apply(&endpoint),
+        Ok((ref s,)) if s == "/api/v2"
+    );
+}
+
+#[test]
+fn test_mount_point_extractor() {
+    let post = named_path!("post", @get / "posts" / u32 /);
+    let endpoint = endpoint::mount(
+        "api/v1",
+        path!(@post / "posts" /)
+            .and(endpoint::mount_point())
+            .map(move |mount_point: MountPoint| post.mounted_url(&mount_point, &[&42]).unwrap()),
+    );
+
+    assert_matches!(
+        local::post("/api/v1/posts").apply(&endpoint),
+        Ok((ref s,)) if s == "/api/v1/posts/42"
+    );
+
+    let endpoint = endpoint::mount_point();
+    assert_matches!(
+        local::get("/posts").apply(&endpoint),
+        Ok((ref mount_point,)) if mount_point.as_str() == ""
+    );
+}
@@ -0,0 +1,116 @@
+use finchers::endpoint::syntax;
+use finchers::endpoint::MountPoint;
+use finchers::local;
+use finchers::prelude::*;
+use finchers::{path, router};
+use matches::assert_matches;
+
+#[test]
+fn test_router() {
+    let endpoint = router! {
+        "/posts" => path!(@get / u32 /).map(|id: u32| format!("post {}", id)),
+        "/users" => path!(@get / u32 /).map(|id: u32| format!("user {}", id)),
+        "/" => path!(@get /).map(|| "index".to_owned()),
+    };
+
+    assert_matches!(
+        local::get("/posts/42").apply(&endpoint),
+        Ok((ref s,)) if s == "post 42"
+    );
+    assert_matches!(
+        local::get("/users/7").apply(&endpoint),
+        Ok((ref s,)) if s == "user 7"
+    );
+    assert_matches!(
+        local::get("/").apply(&endpoint),
+        Ok((ref s,)) if s == "index"
+    );
+}
+
+#[test]
+fn test_router_prefers_longer_prefix() {
+    let endpoint = router! {
+        "/api" => syntax::remains::<String>().map(|path: String| format!("api: {}", path)),
+        "/api/posts" => path!(/ u32 /).map(|id: u32| format!("post {}", id)),
+    };
+
+    assert_matches!(
+        local::get("/api/posts/42").apply(&endpoint),
+        Ok((ref s,)) if s == "post 42"
+    );
+
+    // falls back to the shorter prefix.
+    assert_matches!(
+        local::get("/api/posts/foo").apply(&endpoint),
+        Ok((ref s,)) if s == "api: posts/foo"
+    );
+}
+
+#[test]
+fn test_router_not_matched() {
+    let endpoint = router! {
+        "/posts" => path!(@get / u32 /).map(|id: u32| format!("post {}", id)),
+    };
+
+    assert_matches!(
+        local::get("/users/42").apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 404
+    );
+    assert_matches!(
+        local::get("/posts").apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 404
+    );
+}
+
+#[test]
+fn test_router_method_not_allowed() {
+    let endpoint = router! {
+        "/posts" => path!(@get / u32 /).map(|id: u32| format!("get {}", id)),
+        "/posts" => path!(@put / u32 /).map(|id: u32| format!("put {}", id)),
+    };
+
+    assert_matches!(
+        local::put("/posts/42").apply(&endpoint),
+        Ok((ref s,)) if s == "put 42"
+    );
+    assert_matches!(
+        local::delete("/posts/42").apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 405
+    );
+}
+
+#[test]
+fn test_router_custom_error() {
+    let endpoint = router! {
+        "/posts" => path!(@get / u32 /).map(|id: u32| format!("post {}", id)),
+        "/posts/new" => path!(@get /).map(|| "new".to_owned()),
+    };
+
+    assert_matches!(
+        local::get("/posts/new").apply(&endpoint),
+        Ok((ref s,)) if s == "new"
+    );
+    assert_matches!(
+        local::get("/posts/foo").apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 400
+    );
+}
+
+#[test]
+fn test_router_mount_point() {
+    let mount_point =
+        || endpoint::mount_point().map(|mount_point: MountPoint| mount_point.to_string());
+    let endpoint = router! {
+        "/api/v1" => mount_point(),
+        "/" => mount_point(),
+    };
+
+    assert_matches!(
+        local::get("/api/v1/posts").apply(&endpoint),
+        Ok((ref s,)) if s == "/api/v1"
+    );
+    assert_matches!(
+        local::get("/posts").apply(&endpoint),
+        Ok((ref s,)) if s == ""
+    );
+}
@@ -73,3 +73,78 @@ fn test_path_macro() {
         Ok((ref s,)) if s == "id=42"
     );
 }
+
+#[test]
+fn test_param_matching() {
+    let endpoint = syntax::param_matching::<String>(r"^[a-z0-9-]+$");
+    assert_matches!(
+        local::get("/hello-world").apply(&endpoint),
+        Ok((ref s,)) if s == "hello-world"
+    );
+    assert_matches!(
+        local::get("/Hello%20World").apply(&endpoint),
+        Err(ref e) if e.status_code() == StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn test_param_matching_parse_error() {
+    let endpoint = syntax::param_matching::<u32>(r"^\d+$");
+    assert_matches!(local::get("/42").apply(&endpoint), Ok((42u32,)));
+    // matches the pattern, but overflows.
+    assert_matches!(
+        local::get("/99999999999").apply(&endpoint),
+        Err(ref e) if e.status_code() == StatusCode::NOT_FOUND
+    );
+
+    let endpoint = syntax::param_matching::<u32>(r"^\d+$")
+        .map(|id: u32| format!("id={}", id))
+        .or(syntax::param::<String>().map(|s: String| format!("other={}", s)));
+    let response = local::get("/99999999999").respond(&endpoint);
+    assert_eq!(response.body().to_utf8(), "other=99999999999");
+}
+
+#[test]
+fn test_param_in_range() {
+    let endpoint = syntax::param_in_range(1u32..=12);
+    assert_matches!(local::get("/12").apply(&endpoint), Ok((12u32,)));
+    assert_matches!(
+        local::get("/13").apply(&endpoint),
+        Err(ref e) if e.status_code() == StatusCode::NOT_FOUND
+    );
+    assert_matches!(
+        local::get("/foo").apply(&endpoint),
+        Err(ref e) if e.status_code() == StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn test_param_one_of() {
+    let endpoint = syntax::param_one_of(vec!["asc".to_owned(), "desc".to_owned()]);
+    assert_matches!(
+        local::get("/desc").apply(&endpoint),
+        Ok((ref s,)) if s == "desc"
+    );
+    assert_matches!(
+        local::get("/random").apply(&endpoint),
+        Err(ref e) if e.status_code() == StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn test_constrained_params_fall_through() {
+    let by_id = path!(@get / "posts" / { syntax::param_in_range(1u32..1000) } /)
+        .map(|id: u32| format!("id={}", id));
+    let by_slug = path!(@get / "posts" / { syntax::param_matching::<String>(r"^[a-z-]+$") } /)
+        .map(|slug: String| format!("slug={}", slug));
+    let endpoint = by_id.or(by_slug);
+
+    let response = local::get("/posts/42").respond(&endpoint);
+    assert_eq!(response.body().to_utf8(), "id=42");
+
+    let response = local::get("/posts/hello-world").respond(&endpoint);
+    assert_eq!(response.body().to_utf8(), "slug=hello-world");
+
+    let response = local::get("/posts/1000").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 404);
+}
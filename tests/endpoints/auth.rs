@@ -0,0 +1,113 @@
+use finchers::endpoints::auth;
+use finchers::endpoints::auth::{Basic, Bearer};
+use finchers::local;
+use finchers::prelude::*;
+
+use futures_util::future::ready;
+use matches::assert_matches;
+
+#[test]
+fn test_auth_basic() {
+    let endpoint = auth::basic("secret area", |credentials: Basic| {
+        let password = credentials.password.as_ref().map(|s| s.as_str());
+        ready(Ok(
+            if credentials.username == "alice" && password == Some("secret") {
+                Some(credentials.username.clone())
+            } else {
+                None
+            },
+        ))
+    });
+
+    // alice:secret
+    assert_matches!(
+        local::get("/")
+            .header("authorization", "Basic YWxpY2U6c2VjcmV0")
+            .apply(&endpoint),
+        Ok((ref user,)) if user == "alice"
+    );
+
+    // alice:wrong
+    assert_matches!(
+        local::get("/")
+            .header("authorization", "Basic YWxpY2U6d3Jvbmc=")
+            .apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 401
+    );
+
+    let response = local::get("/").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(
+        response
+            .headers()
+            .get("www-authenticate")
+            .map(|h| h.as_bytes()),
+        Some(&b"Basic realm=\"secret area\""[..])
+    );
+}
+
+#[test]
+fn test_auth_bearer() {
+    let endpoint = auth::bearer("api", |credentials: Bearer| {
+        ready(Ok(if credentials.token == "valid-token" {
+            Some(42u32)
+        } else {
+            None
+        }))
+    });
+
+    assert_matches!(
+        local::get("/")
+            .header("authorization", "Bearer valid-token")
+            .apply(&endpoint),
+        Ok((42,))
+    );
+
+    let response = local::get("/")
+        .header("authorization", "Bearer invalid-token")
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(
+        response
+            .headers()
+            .get("www-authenticate")
+            .map(|h| h.as_bytes()),
+        Some(&b"Bearer realm=\"api\""[..])
+    );
+
+    // scheme mismatch
+    assert_matches!(
+        local::get("/")
+            .header("authorization", "Basic YWxpY2U6c2VjcmV0")
+            .apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 401
+    );
+}
+
+#[test]
+fn test_auth_optional() {
+    let endpoint = auth::bearer("api", |credentials: Bearer| {
+        ready(Ok(if credentials.token == "valid-token" {
+            Some(42u32)
+        } else {
+            None
+        }))
+    })
+    .optional();
+
+    assert_matches!(local::get("/").apply(&endpoint), Ok((None,)));
+
+    assert_matches!(
+        local::get("/")
+            .header("authorization", "Bearer valid-token")
+            .apply(&endpoint),
+        Ok((Some(42),))
+    );
+
+    assert_matches!(
+        local::get("/")
+            .header("authorization", "Bearer invalid-token")
+            .apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 401
+    );
+}
@@ -0,0 +1,139 @@
+use finchers::endpoints::cache::cache;
+use finchers::local;
+use finchers::output::payload::Once;
+use finchers::path;
+use finchers::prelude::*;
+
+use http::Response;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[test]
+fn test_etag_and_not_modified() {
+    let endpoint = path!(@get / "report")
+        .map(|| "expensive report")
+        .wrap(cache());
+
+    let response = local::get("/report").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.body().to_utf8(), "expensive report");
+    let etag = response
+        .headers()
+        .get("etag")
+        .expect("missing ETag")
+        .to_str()
+        .unwrap()
+        .to_owned();
+
+    let response = local::get("/report")
+        .header("if-none-match", etag.as_str())
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 304);
+    assert_eq!(
+        response.headers().get("etag").map(|h| h.to_str().unwrap()),
+        Some(etag.as_str())
+    );
+    assert_eq!(response.body().to_utf8(), "");
+
+    let response = local::get("/report")
+        .header("if-none-match", "\"other\"")
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[test]
+fn test_lru_store() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let endpoint = path!(@get / "report")
+        .map({
+            let count = count.clone();
+            move || {
+                count.fetch_add(1, Ordering::SeqCst);
+                Response::builder()
+                    .header("cache-control", "max-age=60")
+                    .body(Once::new("expensive report"))
+                    .unwrap()
+            }
+        }).wrap(cache().lru(16));
+
+    for _ in 0..3 {
+        let response = local::get("/report").respond(&endpoint);
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(response.body().to_utf8(), "expensive report");
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+
+    let response = local::get("/report")
+        .header("cache-control", "no-cache")
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_lru_store_not_cacheable() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let endpoint = path!(@get / "report")
+        .map({
+            let count = count.clone();
+            move || {
+                count.fetch_add(1, Ordering::SeqCst);
+                Response::builder()
+                    .header("cache-control", "private, max-age=60")
+                    .body(Once::new("expensive report"))
+                    .unwrap()
+            }
+        }).wrap(cache().lru(16));
+
+    for _ in 0..3 {
+        let _ = local::get("/report").respond(&endpoint);
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_lru_store_authorized() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let endpoint = path!(@get / String /)
+        .map({
+            let count = count.clone();
+            move |name: String| {
+                count.fetch_add(1, Ordering::SeqCst);
+                let cache_control = if name == "public" {
+                    "public, max-age=60"
+                } else {
+                    "max-age=60"
+                };
+                Response::builder()
+                    .header("cache-control", cache_control)
+                    .body(Once::new("report"))
+                    .unwrap()
+            }
+        }).wrap(cache().lru(16));
+
+    // The responses to the requests with `Authorization` are not stored by default...
+    for _ in 0..2 {
+        let response = local::get("/private")
+            .header("authorization", "Bearer alice")
+            .respond(&endpoint);
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+
+    // ...and the stored responses are not used for them.
+    let _ = local::get("/private").respond(&endpoint);
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+    let _ = local::get("/private")
+        .header("authorization", "Bearer bob")
+        .respond(&endpoint);
+    assert_eq!(count.load(Ordering::SeqCst), 4);
+
+    // `public` explicitly permits sharing the response.
+    for _ in 0..2 {
+        let response = local::get("/public")
+            .header("authorization", "Bearer alice")
+            .respond(&endpoint);
+        assert_eq!(response.body().to_utf8(), "report");
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 5);
+}
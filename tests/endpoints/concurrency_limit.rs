@@ -0,0 +1,126 @@
+use finchers::endpoints::concurrency_limit::concurrency_limit;
+use finchers::error::Error;
+use finchers::local;
+use finchers::path;
+use finchers::prelude::*;
+
+use futures_util::future::ready;
+use matches::assert_matches;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_concurrency_limit() {
+    let limit = concurrency_limit(1);
+    let endpoint = path!(@get / "report").map(|| "report").wrap(limit.clone());
+
+    for _ in 0..3 {
+        assert_matches!(local::get("/report").apply(&endpoint), Ok(..));
+    }
+    assert_eq!(limit.in_flight(), 0);
+    assert_eq!(limit.queue_depth(), 0);
+}
+
+// Waits until the condition is satisfied by the requests running in other threads.
+fn wait_until(cond: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !cond() {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "the condition has not been satisfied"
+        );
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn test_concurrency_limit_rejected() {
+    let limit = concurrency_limit(1).retry_after(Duration::from_millis(2500));
+    let endpoint = Arc::new(
+        path!(@get / "report")
+            .and_then(|| {
+                thread::sleep(Duration::from_millis(200));
+                ready(Ok::<_, Error>("report"))
+            }).wrap(limit.clone()),
+    );
+
+    let running = {
+        let endpoint = endpoint.clone();
+        thread::spawn(move || local::get("/report").respond(&*endpoint).status().as_u16())
+    };
+    wait_until(|| limit.in_flight() == 1);
+
+    let response = local::get("/report").respond(&*endpoint);
+    assert_eq!(response.status().as_u16(), 503);
+    assert_eq!(
+        response.headers().get("retry-after").map(|h| h.as_bytes()),
+        Some(&b"3"[..])
+    );
+
+    assert_eq!(running.join().unwrap(), 200);
+    assert_eq!(limit.in_flight(), 0);
+}
+
+#[test]
+fn test_concurrency_limit_queued() {
+    let limit = concurrency_limit(1).queue(1, Duration::from_secs(5));
+    let endpoint = Arc::new(
+        path!(@get / "report")
+            .and_then(|| {
+                thread::sleep(Duration::from_millis(200));
+                ready(Ok::<_, Error>("report"))
+            }).wrap(limit.clone()),
+    );
+
+    let running = {
+        let endpoint = endpoint.clone();
+        thread::spawn(move || local::get("/report").respond(&*endpoint).status().as_u16())
+    };
+    wait_until(|| limit.in_flight() == 1);
+
+    let queued = {
+        let endpoint = endpoint.clone();
+        thread::spawn(move || local::get("/report").respond(&*endpoint).status().as_u16())
+    };
+    wait_until(|| limit.queue_depth() == 1);
+
+    // The queue is full.
+    let response = local::get("/report").respond(&*endpoint);
+    assert_eq!(response.status().as_u16(), 503);
+    assert!(response.headers().contains_key("retry-after"));
+
+    // The queued request runs after the running one completes.
+    assert_eq!(running.join().unwrap(), 200);
+    assert_eq!(queued.join().unwrap(), 200);
+    assert_eq!(limit.in_flight(), 0);
+    assert_eq!(limit.queue_depth(), 0);
+}
+
+#[test]
+fn test_concurrency_limit_max_wait() {
+    let limit = concurrency_limit(1).queue(1, Duration::from_millis(20));
+    let endpoint = Arc::new(
+        path!(@get / "report")
+            .and_then(|| {
+                thread::sleep(Duration::from_millis(300));
+                ready(Ok::<_, Error>("report"))
+            }).wrap(limit.clone()),
+    );
+
+    let running = {
+        let endpoint = endpoint.clone();
+        thread::spawn(move || local::get("/report").respond(&*endpoint).status().as_u16())
+    };
+    wait_until(|| limit.in_flight() == 1);
+
+    let response = local::get("/report").respond(&*endpoint);
+    assert_eq!(response.status().as_u16(), 503);
+    assert_eq!(
+        response.headers().get("retry-after").map(|h| h.as_bytes()),
+        Some(&b"1"[..])
+    );
+    assert_eq!(limit.queue_depth(), 0);
+
+    assert_eq!(running.join().unwrap(), 200);
+}
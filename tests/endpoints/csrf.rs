@@ -0,0 +1,65 @@
+use finchers::endpoints::csrf;
+use finchers::endpoints::csrf::CsrfToken;
+use finchers::input::cookie::Key;
+use finchers::local;
+use finchers::prelude::*;
+
+#[test]
+fn test_csrf() {
+    let endpoint = endpoints::body::text()
+        .and(csrf::token())
+        .map(|body: String, token: CsrfToken| {
+            if body.is_empty() {
+                token.to_string()
+            } else {
+                body
+            }
+        }).wrap(csrf::csrf(Key::generate()).exempt("/webhooks"));
+
+    // A safe request issues a new token.
+    let response = local::get("/").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+    let token = response.body().to_utf8().into_owned();
+    let cookie = response
+        .headers()
+        .get("set-cookie")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.split(';').next())
+        .map(ToOwned::to_owned)
+        .expect("missing Set-Cookie");
+
+    // missing token
+    let response = local::post("/")
+        .header("cookie", &*cookie)
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 403);
+
+    // invalid token
+    let response = local::post("/")
+        .header("cookie", &*cookie)
+        .header("x-csrf-token", "invalid")
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 403);
+
+    // valid token in the header field
+    let response = local::post("/")
+        .header("cookie", &*cookie)
+        .header("x-csrf-token", &*token)
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.body().to_utf8(), token);
+
+    // valid token in the form field, and the body is still readable.
+    let form = format!("title=foo&csrf_token={}", token);
+    let response = local::post("/")
+        .header("cookie", &*cookie)
+        .header("content-type", "application/x-www-form-urlencoded")
+        .body(form.clone())
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.body().to_utf8(), form);
+
+    // exempted path
+    let response = local::post("/webhooks/github").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+}
@@ -0,0 +1,28 @@
+use finchers::endpoint::wrapper::before_apply;
+use finchers::local;
+use finchers::prelude::*;
+
+use matches::assert_matches;
+
+#[derive(Debug, Clone, PartialEq)]
+struct RequestId(u32);
+
+#[test]
+fn test_ext() {
+    let endpoint = endpoints::ext::<RequestId>().wrap(before_apply(|cx| {
+        cx.input().extensions_mut().insert(RequestId(42));
+        Ok(())
+    }));
+
+    assert_matches!(local::get("/").apply(&endpoint), Ok((RequestId(42),)));
+}
+
+#[test]
+fn test_ext_missing() {
+    let endpoint = endpoints::ext::<RequestId>();
+
+    assert_matches!(
+        local::get("/").apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 500
+    );
+}
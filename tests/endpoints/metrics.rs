@@ -0,0 +1,52 @@
+use finchers::endpoints::metrics::{instrument, metrics_endpoint, Registry};
+use finchers::local;
+use finchers::path;
+use finchers::prelude::*;
+
+#[test]
+fn test_metrics() {
+    let registry = Registry::new();
+    let endpoint = path!(@get / "hello" / u32)
+        .map(|id: u32| format!("hello, {}", id))
+        .wrap(instrument(&registry, "hello"));
+    let metrics = metrics_endpoint(&registry);
+
+    assert_eq!(
+        local::get("/hello/42").respond(&endpoint).status().as_u16(),
+        200
+    );
+    assert_eq!(
+        local::get("/hello/42").respond(&endpoint).status().as_u16(),
+        200
+    );
+
+    let response = local::get("/metrics").respond(&metrics);
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response
+            .headers()
+            .get("content-type")
+            .map(|h| h.to_str().unwrap()),
+        Some("text/plain; version=0.0.4; charset=utf-8")
+    );
+    let body = response.body().to_utf8();
+    assert!(body.contains("http_requests_total{method=\"GET\",route=\"hello\",status=\"2xx\"} 2\n"));
+    assert!(body.contains("http_requests_in_flight{method=\"GET\",route=\"hello\"} 0\n"));
+    assert!(body.contains(
+        "http_response_size_bytes_sum{method=\"GET\",route=\"hello\",status=\"2xx\"} 18\n"
+    ));
+}
+
+#[test]
+fn test_metrics_not_matched() {
+    let registry = Registry::new();
+    let endpoint = path!(@get / "hello")
+        .map(|| "hello")
+        .wrap(instrument(&registry, "hello"));
+
+    assert_eq!(
+        local::get("/goodbye").respond(&endpoint).status().as_u16(),
+        404
+    );
+    assert!(!registry.render().contains("http_requests_total{"));
+}
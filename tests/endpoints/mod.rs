@@ -1,3 +1,21 @@
+mod auth;
 mod body;
+mod cache;
+mod concurrency_limit;
+#[cfg(feature = "secure")]
+mod csrf;
+mod ext;
 mod header;
+#[cfg(feature = "jwt")]
+mod jwt;
+mod metrics;
+mod normalize_path;
+mod proxy;
 mod query;
+mod rate_limit;
+mod remote_addr;
+mod request_id;
+mod session;
+mod state;
+mod timeout;
+mod tracing;
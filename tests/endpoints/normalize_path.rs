@@ -0,0 +1,76 @@
+use finchers::endpoints::normalize_path::{normalize_path, TrailingSlash};
+use finchers::error::Error;
+use finchers::local;
+use finchers::path;
+use finchers::prelude::*;
+use futures_util::future::ready;
+
+#[test]
+fn test_normalize_path_ignore() {
+    let endpoint = path!(@get / "users" / u32 /)
+        .map(|id: u32| format!("user {}", id))
+        .wrap(normalize_path());
+
+    for path in &[
+        "/users/42",
+        "/users/42/",
+        "/users///42/",
+        "/static/../users/./42",
+    ] {
+        let response = local::get(*path).respond(&endpoint);
+        assert_eq!(response.status().as_u16(), 200, "path = {}", path);
+        assert_eq!(response.body().to_utf8(), "user 42");
+    }
+}
+
+#[test]
+fn test_normalize_path_strict() {
+    let endpoint = path!(@get / "users" / u32 /)
+        .map(|id: u32| format!("user {}", id))
+        .wrap(normalize_path().trailing_slash(TrailingSlash::Strict));
+
+    let response = local::get("/users//42").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = local::get("/users/42/").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[test]
+fn test_normalize_path_redirect() {
+    let endpoint = path!(@get / "users" / u32 /)
+        .map(|id: u32| format!("user {}", id))
+        .wrap(normalize_path().trailing_slash(TrailingSlash::Redirect));
+
+    let response = local::get("/users/42").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = local::get("/users//42/?page=2").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 308);
+    assert_eq!(
+        response
+            .headers()
+            .get("location")
+            .map(|h| h.to_str().unwrap()),
+        Some("/users/42?page=2")
+    );
+}
+
+#[test]
+fn test_normalize_path_not_matched() {
+    let normalized = path!(@get / "users" / u32 /)
+        .map(|id: u32| format!("user {}", id))
+        .wrap(normalize_path());
+    let other = endpoint::apply_fn(|cx| {
+        let path = cx.input().uri().path().to_owned();
+        Ok(ready(Ok::<_, Error>((path,))))
+    });
+    let endpoint = normalized.or(other);
+
+    let response = local::get("/users//42/").respond(&endpoint);
+    assert_eq!(response.body().to_utf8(), "user 42");
+
+    let response = local::get("/posts//42/").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.body().to_utf8(), "/posts//42/");
+}
@@ -0,0 +1,94 @@
+use finchers::endpoint::{Context, EndpointResult};
+use finchers::endpoints::proxy::{trusted_proxies, Cidr};
+use finchers::error::Error;
+use finchers::local;
+use finchers::prelude::*;
+
+use futures_util::future::{ready, Ready};
+use matches::assert_matches;
+use std::net::SocketAddr;
+
+#[derive(Debug)]
+struct ClientInfo;
+
+impl<'a> Endpoint<'a> for ClientInfo {
+    type Output = (Option<SocketAddr>, String, Option<String>);
+    type Future = Ready<Result<Self::Output, Error>>;
+
+    fn apply(&'a self, cx: &mut Context<'_>) -> EndpointResult<Self::Future> {
+        let input = cx.input();
+        Ok(ready(Ok((
+            input.remote_addr(),
+            input.scheme().to_owned(),
+            input.host().map(ToOwned::to_owned),
+        ))))
+    }
+}
+
+fn cidrs() -> Vec<Cidr> {
+    vec!["10.0.0.0/8".parse().unwrap()]
+}
+
+#[test]
+fn test_trusted_proxies_forwarded() {
+    let endpoint = ClientInfo.wrap(trusted_proxies(cidrs()));
+
+    assert_matches!(
+        local::get("/")
+            .remote_addr(([10, 0, 0, 1], 12345))
+            .header("host", "10.0.0.1:8080")
+            .header("forwarded", "for=192.0.2.60;proto=https;host=example.com, for=10.0.0.2")
+            .apply(&endpoint),
+        Ok((Some(addr), ref scheme, Some(ref host)))
+            if addr.ip().to_string() == "192.0.2.60" && scheme == "https" && host == "example.com"
+    );
+}
+
+#[test]
+fn test_trusted_proxies_x_forwarded() {
+    let endpoint = ClientInfo.wrap(trusted_proxies(cidrs()));
+
+    // The leftmost address is spoofed by the client.
+    assert_matches!(
+        local::get("/")
+            .remote_addr(([10, 0, 0, 1], 12345))
+            .header("x-forwarded-for", "1.1.1.1, 192.0.2.60")
+            .header("x-forwarded-proto", "https")
+            .apply(&endpoint),
+        Ok((Some(addr), ref scheme, None))
+            if addr.ip().to_string() == "192.0.2.60" && scheme == "https"
+    );
+}
+
+#[test]
+fn test_untrusted_peer() {
+    let endpoint = ClientInfo.wrap(trusted_proxies(cidrs()));
+
+    assert_matches!(
+        local::get("/")
+            .remote_addr(([192, 0, 2, 1], 12345))
+            .header("host", "example.org")
+            .header("x-forwarded-for", "1.1.1.1")
+            .header("x-forwarded-proto", "https")
+            .header("x-forwarded-host", "example.com")
+            .apply(&endpoint),
+        Ok((Some(addr), ref scheme, Some(ref host)))
+            if addr.ip().to_string() == "192.0.2.1" && scheme == "http" && host == "example.org"
+    );
+}
+
+#[test]
+fn test_secure_connection() {
+    assert_matches!(
+        local::get("/")
+            .remote_addr(([192, 0, 2, 1], 12345))
+            .secure(true)
+            .apply(&ClientInfo),
+        Ok((Some(addr), ref scheme, None))
+            if addr.ip().to_string() == "192.0.2.1" && scheme == "https"
+    );
+    assert_matches!(
+        local::get("/").apply(&ClientInfo),
+        Ok((None, ref scheme, None)) if scheme == "http"
+    );
+}
@@ -0,0 +1,108 @@
+use finchers::endpoints::rate_limit::{rate_limit, Key, Quota};
+use finchers::local;
+use finchers::path;
+use finchers::prelude::*;
+
+use http::header::HeaderName;
+use matches::assert_matches;
+
+#[test]
+fn test_rate_limit() {
+    let endpoint = path!(@get / "search").map(|| "results").wrap(rate_limit(
+        Quota::per_hour(2),
+        Key::header(HeaderName::from_static("x-api-key")),
+    ));
+
+    for _ in 0..2 {
+        assert_matches!(
+            local::get("/search")
+                .header("x-api-key", "alice")
+                .apply(&endpoint),
+            Ok(..)
+        );
+    }
+
+    let response = local::get("/search")
+        .header("x-api-key", "alice")
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 429);
+    assert!(response.headers().contains_key("retry-after"));
+    assert_eq!(
+        response
+            .headers()
+            .get("ratelimit-limit")
+            .map(|h| h.as_bytes()),
+        Some(&b"2"[..])
+    );
+    assert_eq!(
+        response
+            .headers()
+            .get("ratelimit-remaining")
+            .map(|h| h.as_bytes()),
+        Some(&b"0"[..])
+    );
+
+    // the other clients are not affected.
+    assert_matches!(
+        local::get("/search")
+            .header("x-api-key", "bob")
+            .apply(&endpoint),
+        Ok(..)
+    );
+
+    // the requests which do not match are not counted.
+    assert_matches!(
+        local::get("/other")
+            .header("x-api-key", "bob")
+            .apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 404
+    );
+    assert_matches!(
+        local::get("/search")
+            .header("x-api-key", "bob")
+            .apply(&endpoint),
+        Ok(..)
+    );
+}
+
+#[test]
+fn test_rate_limit_custom_key() {
+    let endpoint = path!(@get /).map(|| "hello").wrap(rate_limit(
+        Quota::per_hour(1),
+        Key::custom(|input| Some(input.uri().query().unwrap_or("").to_owned())),
+    ));
+
+    assert_matches!(local::get("/?a").apply(&endpoint), Ok(..));
+    assert_matches!(
+        local::get("/?a").apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 429
+    );
+    assert_matches!(local::get("/?b").apply(&endpoint), Ok(..));
+}
+
+#[test]
+fn test_rate_limit_remote_addr() {
+    let endpoint = path!(@get /)
+        .map(|| "hello")
+        .wrap(rate_limit(Quota::per_hour(1), Key::remote_addr()));
+
+    assert_matches!(
+        local::get("/")
+            .remote_addr(([10, 0, 0, 1], 1000))
+            .apply(&endpoint),
+        Ok(..)
+    );
+    // the port number is ignored.
+    assert_matches!(
+        local::get("/")
+            .remote_addr(([10, 0, 0, 1], 2000))
+            .apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 429
+    );
+    assert_matches!(
+        local::get("/")
+            .remote_addr(([10, 0, 0, 2], 1000))
+            .apply(&endpoint),
+        Ok(..)
+    );
+}
@@ -0,0 +1,18 @@
+use finchers::local;
+use finchers::prelude::*;
+
+use matches::assert_matches;
+use std::net::SocketAddr;
+
+#[test]
+fn test_remote_addr() {
+    let endpoint = endpoints::remote_addr();
+
+    let addr: SocketAddr = ([192, 168, 0, 1], 54321).into();
+    assert_matches!(
+        local::get("/").remote_addr(addr).apply(&endpoint),
+        Ok((Some(a),)) if a == addr
+    );
+
+    assert_matches!(local::get("/").apply(&endpoint), Ok((None,)));
+}
@@ -0,0 +1,95 @@
+use finchers::endpoints::request_id::{request_id, RequestId};
+use finchers::error::bad_request;
+use finchers::local;
+use finchers::path;
+use finchers::prelude::*;
+use futures_util::future::ready;
+use http::header::HeaderName;
+use serde_json::json;
+
+#[test]
+fn test_request_id_propagated() {
+    let endpoint = path!(@get / "hello")
+        .and(endpoints::ext::<RequestId>())
+        .map(|id: RequestId| id.to_string())
+        .wrap(request_id());
+
+    let response = local::get("/hello")
+        .header("x-request-id", "abc-123")
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.body().to_utf8(), "abc-123");
+    assert_eq!(
+        response
+            .headers()
+            .get("x-request-id")
+            .map(|h| h.to_str().unwrap()),
+        Some("abc-123")
+    );
+}
+
+#[test]
+fn test_request_id_generated() {
+    let endpoint = path!(@get / "hello")
+        .map(|| "hello")
+        .wrap(request_id().generator(|| "generated".to_owned()));
+
+    let response = local::get("/hello")
+        .header("x-request-id", "invalid id")
+        .respond(&endpoint);
+    assert_eq!(
+        response
+            .headers()
+            .get("x-request-id")
+            .map(|h| h.to_str().unwrap()),
+        Some("generated")
+    );
+}
+
+#[test]
+fn test_request_id_in_error_response() {
+    let endpoint = path!(@get / "hello")
+        .map(|| "hello")
+        .wrap(request_id().header_name(HeaderName::from_static("x-correlation-id")));
+
+    let response = local::get("/goodbye")
+        .header("x-correlation-id", "abc-123")
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 404);
+    assert_eq!(
+        response
+            .headers()
+            .get("x-correlation-id")
+            .map(|h| h.to_str().unwrap()),
+        Some("abc-123")
+    );
+    assert!(response.body().to_utf8().ends_with("(request id: abc-123)"));
+}
+
+#[test]
+fn test_request_id_in_serialized_error() {
+    let endpoint = path!(@get / "hello")
+        .and_then(|| ready(Err::<&str, _>(bad_request("oops"))))
+        .wrap(request_id());
+
+    let err = local::get("/hello")
+        .header("x-request-id", "abc-123")
+        .apply(&endpoint)
+        .err()
+        .expect("the endpoint should fail");
+    assert_eq!(err.request_id(), Some("abc-123"));
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        json!({
+            "code": 400,
+            "description": "oops",
+            "request_id": "abc-123",
+        })
+    );
+
+    let response = local::get("/hello")
+        .header("x-request-id", "abc-123")
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(response.body().to_utf8(), "oops\n(request id: abc-123)");
+}
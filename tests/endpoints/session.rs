@@ -0,0 +1,86 @@
+use finchers::endpoints::session;
+use finchers::endpoints::session::{MemoryStore, Session};
+use finchers::error::Error;
+use finchers::local;
+use finchers::prelude::*;
+use finchers::{path, routes};
+
+fn session_cookie<T>(response: &http::Response<T>) -> Option<String> {
+    response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .filter_map(|h| h.split(';').next())
+        .find(|h| h.starts_with("session-id="))
+        .map(ToOwned::to_owned)
+}
+
+#[test]
+fn test_session() {
+    let session = session::session(MemoryStore::new());
+
+    let endpoint = routes![
+        path!(@get / "count")
+            .and(session.clone())
+            .map(|session: Session| -> Result<String, Error> {
+                let count = session.get::<u32>("count")?.unwrap_or(0) + 1;
+                session.set("count", count)?;
+                Ok(format!("{}", count))
+            }),
+        path!(@post / "login")
+            .and(session.clone())
+            .map(|session: Session| {
+                session.rotate();
+                "logged in"
+            }),
+        path!(@post / "logout")
+            .and(session)
+            .map(|session: Session| {
+                session.destroy();
+                "logged out"
+            }),
+    ];
+
+    let response = local::get("/count").respond(&endpoint);
+    assert_eq!(response.body().to_utf8(), "1");
+    let cookie = session_cookie(&response).expect("missing session cookie");
+
+    let response = local::get("/count")
+        .header("cookie", &*cookie)
+        .respond(&endpoint);
+    assert_eq!(response.body().to_utf8(), "2");
+
+    // The session identifier is renewed, but the values are retained.
+    let response = local::post("/login")
+        .header("cookie", &*cookie)
+        .respond(&endpoint);
+    let new_cookie = session_cookie(&response).expect("missing session cookie");
+    assert_ne!(cookie, new_cookie);
+
+    let response = local::get("/count")
+        .header("cookie", &*cookie)
+        .respond(&endpoint);
+    assert_eq!(response.body().to_utf8(), "1");
+
+    let response = local::get("/count")
+        .header("cookie", &*new_cookie)
+        .respond(&endpoint);
+    assert_eq!(response.body().to_utf8(), "3");
+
+    // The session is discarded.
+    local::post("/logout")
+        .header("cookie", &*new_cookie)
+        .respond(&endpoint);
+    let response = local::get("/count")
+        .header("cookie", &*new_cookie)
+        .respond(&endpoint);
+    assert_eq!(response.body().to_utf8(), "1");
+}
+
+#[test]
+fn test_session_not_stored_if_empty() {
+    let endpoint = session::session(MemoryStore::new()).map(|_: Session| "");
+    let response = local::get("/").respond(&endpoint);
+    assert!(session_cookie(&response).is_none());
+}
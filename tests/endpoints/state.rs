@@ -0,0 +1,34 @@
+use finchers::local;
+use finchers::path;
+use finchers::prelude::*;
+
+use matches::assert_matches;
+use std::sync::Arc;
+
+#[derive(Debug)]
+struct Counter(u32);
+
+#[test]
+fn test_state() {
+    let endpoint = path!(@get / "count")
+        .and(endpoints::state::<Counter>())
+        .map(|counter: Arc<Counter>| counter.0);
+
+    assert_matches!(
+        local::get("/count")
+            .state(Counter(42))
+            .state(String::from("unused"))
+            .apply(&endpoint),
+        Ok((42,))
+    );
+}
+
+#[test]
+fn test_state_missing() {
+    let endpoint = endpoints::state::<Counter>();
+
+    assert_matches!(
+        local::get("/").apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 500
+    );
+}
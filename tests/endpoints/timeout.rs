@@ -0,0 +1,71 @@
+use finchers::endpoints::timeout::timeout;
+use finchers::error::Error;
+use finchers::local;
+use finchers::prelude::*;
+
+use futures_util::future::{empty, ready};
+use http::StatusCode;
+use hyper::Body;
+use matches::assert_matches;
+use std::time::Duration;
+
+#[test]
+fn test_timeout_completed() {
+    let endpoint = endpoint::value("Foo")
+        .and_then(|s| ready(Ok(s)))
+        .wrap(timeout(Duration::from_secs(5)));
+
+    assert_matches!(
+        local::get("/").apply(&endpoint),
+        Ok((ref s,)) if *s == "Foo"
+    )
+}
+
+#[test]
+fn test_timeout_elapsed() {
+    let endpoint = endpoint::value("Foo")
+        .and_then(|_| empty::<Result<&'static str, Error>>())
+        .wrap(timeout(Duration::from_millis(10)));
+
+    assert_matches!(
+        local::get("/").apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 503
+    )
+}
+
+#[test]
+fn test_timeout_status() {
+    let endpoint = endpoint::value("Foo")
+        .and_then(|_| empty::<Result<&'static str, Error>>())
+        .wrap(timeout(Duration::from_millis(10)).status(StatusCode::GATEWAY_TIMEOUT));
+
+    assert_matches!(
+        local::get("/").apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 504
+    )
+}
+
+#[test]
+fn test_body_read_timeout() {
+    let endpoint = endpoints::body::text();
+
+    // The sender is kept alive so that the body never completes.
+    let (_sender, body) = Body::channel();
+    assert_matches!(
+        local::post("/")
+            .body(body)
+            .body_read_timeout(Duration::from_millis(10))
+            .apply(&endpoint),
+        Err(ref e) if e.status_code().as_u16() == 408
+    )
+}
+
+#[test]
+fn test_request_timeout() {
+    let endpoint = endpoint::value("Foo").and_then(|_| empty::<Result<&'static str, Error>>());
+
+    let response = local::get("/")
+        .request_timeout(Duration::from_millis(10))
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 503);
+}
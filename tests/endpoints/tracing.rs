@@ -0,0 +1,75 @@
+use finchers::endpoints::tracing::{tracing, InMemoryExporter, TraceContext};
+use finchers::error::Error;
+use finchers::local;
+use finchers::path;
+use finchers::prelude::*;
+use futures_util::future::ready;
+
+#[test]
+fn test_tracing_continues_trace() {
+    let exporter = InMemoryExporter::new();
+    let endpoint = path!(@get / "hello")
+        .and(endpoints::ext::<TraceContext>())
+        .map(|cx: TraceContext| cx.to_traceparent())
+        .wrap(tracing(exporter.clone()));
+
+    let response = local::get("/hello")
+        .header(
+            "traceparent",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+        )
+        .header("tracestate", "congo=t61rcWkgMzE")
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+
+    let spans = exporter.spans();
+    assert_eq!(spans.len(), 1);
+    let span = &spans[0];
+    assert_eq!(span.name, "GET /hello");
+    assert_eq!(span.status.map(|s| s.as_u16()), Some(200));
+    assert_eq!(span.context.trace_id(), "0af7651916cd43dd8448eb211c80319c");
+    assert_eq!(span.context.parent_span_id(), Some("b7ad6b7169203331"));
+    assert_eq!(span.context.tracestate(), Some("congo=t61rcWkgMzE"));
+    assert!(span.context.is_sampled());
+    assert_eq!(response.body().to_utf8(), span.context.to_traceparent());
+}
+
+#[test]
+fn test_tracing_starts_new_trace() {
+    let exporter = InMemoryExporter::new();
+    let endpoint = path!(@get / "hello")
+        .and(endpoints::body::text())
+        .map(|body: String| body)
+        .wrap(tracing(exporter.clone()).name("hello"));
+
+    let _ = local::get("/hello")
+        .header("traceparent", "invalid")
+        .respond(&endpoint);
+
+    let spans = exporter.spans();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0].name, "hello");
+    assert_eq!(spans[0].context.trace_id().len(), 32);
+    assert_eq!(spans[0].context.parent_span_id(), None);
+    assert!(spans[0].status.is_some());
+}
+
+#[test]
+fn test_tracing_not_matched() {
+    let exporter = InMemoryExporter::new();
+    let traced = path!(@get / "hello")
+        .map(|| "hello")
+        .wrap(tracing(exporter.clone()));
+    let other = path!(@get / "other")
+        .and(endpoint::apply_fn(|cx| {
+            let traced = cx.input().extensions().get::<TraceContext>().is_some();
+            Ok(ready(Ok::<_, Error>((traced,))))
+        }))
+        .map(|traced: bool| format!("traced: {}", traced));
+    let endpoint = traced.or(other);
+
+    let response = local::get("/other").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.body().to_utf8(), "traced: false");
+    assert!(exporter.spans().is_empty());
+}
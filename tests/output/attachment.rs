@@ -0,0 +1,85 @@
+use finchers::local;
+use finchers::output::Attachment;
+use finchers::path;
+use finchers::prelude::*;
+use futures::stream;
+use std::io;
+
+#[test]
+fn test_attachment_from_bytes() {
+    let endpoint = path!(@get / "report").map(|| {
+        Attachment::from_bytes("id,name\n1,alice\n")
+            .filename("レポート 2018.csv")
+            .content_type("text/csv".parse().unwrap())
+    });
+
+    let response = local::get("/report").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response
+            .headers()
+            .get("content-disposition")
+            .map(|h| h.to_str().unwrap()),
+        Some(
+            "attachment; filename=\"____ 2018.csv\"; \
+             filename*=UTF-8''%E3%83%AC%E3%83%9D%E3%83%BC%E3%83%88%202018.csv"
+        )
+    );
+    assert_eq!(
+        response
+            .headers()
+            .get("content-type")
+            .map(|h| h.to_str().unwrap()),
+        Some("text/csv")
+    );
+    assert_eq!(
+        response
+            .headers()
+            .get("content-length")
+            .map(|h| h.to_str().unwrap()),
+        Some("16")
+    );
+    assert_eq!(response.body().to_utf8(), "id,name\n1,alice\n");
+}
+
+#[test]
+fn test_attachment_from_reader() {
+    let endpoint = path!(@get / "report").map(|| {
+        Attachment::from_reader(io::Cursor::new(b"hello, world".to_vec())).filename("a\"b.txt")
+    });
+
+    let response = local::get("/report").respond(&endpoint);
+    assert_eq!(
+        response
+            .headers()
+            .get("content-disposition")
+            .map(|h| h.to_str().unwrap()),
+        Some("attachment; filename=\"a_b.txt\"; filename*=UTF-8''a%22b.txt")
+    );
+    assert_eq!(
+        response
+            .headers()
+            .get("content-type")
+            .map(|h| h.to_str().unwrap()),
+        Some("application/octet-stream")
+    );
+    assert_eq!(response.body().to_utf8(), "hello, world");
+}
+
+#[test]
+fn test_attachment_from_stream() {
+    let endpoint = path!(@get / "report").map(|| {
+        let chunks = stream::iter_ok::<_, io::Error>(vec!["hello, ", "world"]);
+        Attachment::from_stream(chunks).content_length(12)
+    });
+
+    let response = local::get("/report").respond(&endpoint);
+    assert_eq!(
+        response
+            .headers()
+            .get("content-disposition")
+            .map(|h| h.to_str().unwrap()),
+        Some("attachment")
+    );
+    assert_eq!(response.body().to_utf8(), "hello, world");
+}
@@ -0,0 +1,66 @@
+use finchers::local;
+use finchers::output::Json;
+use finchers::path;
+use finchers::prelude::*;
+use hyperx::header::{CacheControl, CacheDirective, Location};
+
+#[test]
+fn test_with_header() {
+    let endpoint = path!(@get / "posts" / u32).map(|id: u32| {
+        Json(id)
+            .with_header("x-post-id", id.to_string())
+            .with_header("content-type", "application/vnd.post+json")
+    });
+
+    let response = local::get("/posts/42").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response
+            .headers()
+            .get("x-post-id")
+            .map(|h| h.to_str().unwrap()),
+        Some("42")
+    );
+    assert_eq!(
+        response
+            .headers()
+            .get_all("content-type")
+            .iter()
+            .map(|h| h.to_str().unwrap())
+            .collect::<Vec<_>>(),
+        vec!["application/vnd.post+json"]
+    );
+}
+
+#[test]
+fn test_with_typed_header() {
+    let endpoint = path!(@get / "posts").map(|| {
+        "created"
+            .with_typed_header(Location::new("/posts/1"))
+            .with_typed_header(CacheControl(vec![
+                CacheDirective::NoCache,
+                CacheDirective::MaxAge(60),
+            ]))
+    });
+
+    let response = local::get("/posts").respond(&endpoint);
+    assert_eq!(
+        response.headers().get("location").map(|h| h.as_bytes()),
+        Some(&b"/posts/1"[..])
+    );
+    assert_eq!(
+        response
+            .headers()
+            .get("cache-control")
+            .map(|h| h.as_bytes()),
+        Some(&b"no-cache, max-age=60"[..])
+    );
+}
+
+#[test]
+fn test_with_invalid_header() {
+    let endpoint = path!(@get / "posts").map(|| "posts".with_header("x-bad", "a\nb"));
+
+    let response = local::get("/posts").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 500);
+}
@@ -0,0 +1,4 @@
+mod attachment;
+mod header;
+mod redirect;
+mod template;
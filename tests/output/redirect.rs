@@ -0,0 +1,45 @@
+use finchers::local;
+use finchers::named_path;
+use finchers::output::Redirect;
+use finchers::path;
+use finchers::prelude::*;
+
+#[test]
+fn test_redirect_status() {
+    let endpoint = path!(@get / "old" / u32 /).map(|kind: u32| match kind {
+        0 => Redirect::to("/new"),
+        1 => Redirect::permanent("/new"),
+        2 => Redirect::see_other("/new"),
+        _ => Redirect::temporary("/new"),
+    });
+
+    for &(kind, status) in &[(0, 302), (1, 308), (2, 303), (3, 307)] {
+        let response = local::get(&*format!("/old/{}", kind)).respond(&endpoint);
+        assert_eq!(response.status().as_u16(), status);
+        assert_eq!(
+            response.headers().get("location").map(|h| h.as_bytes()),
+            Some(&b"/new"[..])
+        );
+    }
+}
+
+#[test]
+fn test_redirect_to_named_path() {
+    let post = named_path!("post", @get / "posts" / String /);
+    let location = post.url(&[&"hello world"]).unwrap();
+    assert_eq!(location, "/posts/hello%20world");
+
+    let endpoint = path!(@post / "posts" /).map(move || Redirect::see_other(location.clone()));
+    let response = local::post("/posts").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 303);
+
+    let location = response
+        .headers()
+        .get("location")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    let endpoint = post.map(|title: String| title);
+    let response = local::get(location).respond(&endpoint);
+    assert_eq!(response.body().to_utf8(), "hello world");
+}
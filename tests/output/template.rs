@@ -0,0 +1,75 @@
+use failure::format_err;
+use finchers::local;
+use finchers::output::template::{Renderer, Template};
+use finchers::path;
+use finchers::prelude::*;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+fn render(name: &str, context: &Value) -> Result<String, failure::Error> {
+    match name {
+        "greeting.html" => Ok(format!(
+            "<p>Hello, {}</p>",
+            context["name"].as_str().unwrap_or("")
+        )),
+        _ => Err(format_err!("template not found: {}", name)),
+    }
+}
+
+#[test]
+fn test_template() {
+    let endpoint = path!(@get / "greet" / String)
+        .map(|name: String| Template::new("greeting.html", json!({ "name": name })));
+
+    let response = local::get("/greet/alice")
+        .state(Renderer::new(render))
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response
+            .headers()
+            .get("content-type")
+            .map(|h| h.to_str().unwrap()),
+        Some("text/html; charset=utf-8")
+    );
+    assert_eq!(response.body().to_utf8(), "<p>Hello, alice</p>");
+}
+
+#[test]
+fn test_template_render_error() {
+    let endpoint = path!(@get / "missing").map(|| Template::new("missing.html", ()));
+
+    let response = local::get("/missing")
+        .state(Renderer::new(render))
+        .respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 500);
+}
+
+#[test]
+fn test_template_without_renderer() {
+    let endpoint = path!(@get / "greet").map(|| Template::new("greeting.html", ()));
+
+    let response = local::get("/greet").respond(&endpoint);
+    assert_eq!(response.status().as_u16(), 500);
+}
+
+#[test]
+fn test_template_reloading() {
+    let loaded = Arc::new(AtomicUsize::new(0));
+    let renderer = Renderer::reloading({
+        let loaded = loaded.clone();
+        move || {
+            loaded.fetch_add(1, Ordering::SeqCst);
+            Ok(render)
+        }
+    });
+
+    for _ in 0..2 {
+        let body = renderer
+            .render("greeting.html", &json!({ "name": "bob" }))
+            .unwrap();
+        assert_eq!(body, "<p>Hello, bob</p>");
+    }
+    assert_eq!(loaded.load(Ordering::SeqCst), 2);
+}
@@ -4,15 +4,20 @@
 extern crate bytes;
 extern crate failure;
 extern crate finchers;
+extern crate futures;
 extern crate futures_util;
 extern crate http;
+extern crate hyper;
+extern crate hyperx;
 extern crate matches;
 extern crate mime;
 extern crate serde;
+extern crate serde_json;
 
 //mod codegen;
 mod endpoint;
 mod endpoints;
+mod output;
 
 #[test]
 fn smoketest() {